@@ -1,32 +1,401 @@
 use anyhow::{anyhow, Context, Result};
-use chrono::{Local, TimeZone};
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use crossterm::ExecutableCommand;
 use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 use ratatui::Terminal;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::cmp::Reverse;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::env;
+use std::ffi::OsString;
 use std::fs::{self, File};
 use std::io::{self, stdout, BufRead, BufReader, IsTerminal, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::thread;
 use std::time::Duration as StdDuration;
-use std::time::UNIX_EPOCH;
+use std::time::{Instant, UNIX_EPOCH};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 const INTERNAL_TYPES: [&str; 3] = ["file-history-snapshot", "progress", "queue-operation"];
 
+// Rough blended USD rates per 1K tokens, matched by longest model-name prefix.
+const DEFAULT_COST_RATES: &[(&str, f64)] = &[
+    ("claude-opus", 0.045),
+    ("claude-sonnet", 0.009),
+    ("claude-haiku", 0.0024),
+    ("gpt-5", 0.0056),
+    ("gpt-4.1", 0.005),
+    ("o3", 0.005),
+    ("o4-mini", 0.0028),
+];
+const CHARS_PER_TOKEN: f64 = 4.0;
+/// Longest `cs stats` activity window, in days (about ten years).
+const MAX_STATS_DAYS: usize = 3660;
+/// `schema_version` of the `list --json` and `view --json` documents. Bump it
+/// whenever a field is renamed, removed, or changes type.
+const JSON_SCHEMA_VERSION: u32 = 1;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+static IGNORE_RULES: OnceLock<IgnoreRules> = OnceLock::new();
+static DISPLAY_ZONE: OnceLock<DisplayZone> = OnceLock::new();
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    /// Overrides/additions for `DEFAULT_COST_RATES`, keyed by model-name prefix.
+    cost_rates: HashMap<String, f64>,
+    /// Source column labels keyed by source cache key (`claudecode`, `codex`).
+    source_labels: HashMap<String, String>,
+    /// Use Nerd Font glyphs as source labels when no explicit label is set.
+    nerd_font_glyphs: bool,
+    /// Session files above this size (MiB) are flagged as large. Defaults to 1.
+    large_file_mb: Option<f64>,
+    /// Default `cs stats` activity window in days. Defaults to 14.
+    stats_days: Option<usize>,
+    /// TUI detail-view colors keyed by `user`, `assistant`, `tool`, `thinking`,
+    /// `error` (color names, `#rrggbb`, or a 0-255 palette index).
+    detail_colors: HashMap<String, String>,
+    /// Trailing project path components kept in full when shortening deep
+    /// paths (`~/w/…/group/crate`). Defaults to 2; 0 disables collapsing.
+    project_tail_components: Option<usize>,
+    /// Extra arguments appended when resuming, keyed by source cache key
+    /// (e.g. `{"claudecode": ["--permission-mode", "plan"]}`).
+    resume_args: HashMap<String, Vec<String>>,
+    /// Time zone for displayed timestamps: `local` (default), `UTC`, or a
+    /// fixed offset like `+05:30`. `CS_RS_TZ` overrides it.
+    time_zone: Option<String>,
+    /// Display names for project paths (`~` allowed); subdirectories keep
+    /// their relative tail, e.g. `acme/api`.
+    project_aliases: HashMap<String, String>,
+    /// Always show project git branches in `cs list` (like `--show-branch`).
+    show_git_branch: bool,
+    /// Let `--project` also match the project name as a subsequence
+    /// (like `--project-fuzzy`).
+    fuzzy_project: bool,
+    /// How zsh runs the resume command: `interactive` (`-ic`, default, reads
+    /// `.zshrc`), `login` (`-lc`, reads `.zprofile`), or `plain` (`-c`).
+    resume_shell: Option<String>,
+    /// Resume every session with the newest model used for its source (like
+    /// `cs resume --model-latest`).
+    resume_with_latest_model: bool,
+    /// Collapse runs of consecutive tool calls in `cs view` (like
+    /// `--collapse-tools`; `--no-collapse` overrides it).
+    collapse_tools: bool,
+}
+
+impl Config {
+    fn file_path() -> PathBuf {
+        home_dir().join(".config").join("cs-rs").join("config.json")
+    }
+
+    fn load() -> Self {
+        let path = Self::file_path();
+        let Ok(raw) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&raw) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("cs-rs: ignoring invalid config {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn large_file_threshold_bytes(&self) -> u64 {
+        let mb = self.large_file_mb.filter(|mb| *mb > 0.0).unwrap_or(1.0);
+        (mb * 1_048_576.0) as u64
+    }
+
+    fn detail_color(&self, role: &str, default: Color) -> Color {
+        self.detail_colors
+            .get(role)
+            .and_then(|color| color.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn display_zone(&self) -> DisplayZone {
+        let env = std::env::var("CS_RS_TZ").ok();
+        let Some(raw) = env.as_deref().or(self.time_zone.as_deref()) else {
+            return DisplayZone::Local;
+        };
+        DisplayZone::parse(raw).unwrap_or_else(|| {
+            eprintln!("cs-rs: ignoring unknown time zone {raw:?} (use local, UTC, or +HH:MM)");
+            DisplayZone::Local
+        })
+    }
+
+    /// The alias for `project` (a `~`-shortened path) or one of its parents,
+    /// preferring the most specific configured path.
+    fn project_alias(&self, project: &str) -> Option<String> {
+        let home = home_dir();
+        let home = home.to_string_lossy();
+        self.project_aliases
+            .iter()
+            .filter_map(|(path, alias)| {
+                let path = path.trim_end_matches('/');
+                let path = match path.strip_prefix(home.as_ref()) {
+                    Some(rest) => format!("~{rest}"),
+                    None => path.to_string(),
+                };
+                let rest = project.strip_prefix(path.as_str())?;
+                (rest.is_empty() || rest.starts_with('/'))
+                    .then(|| (path.len(), format!("{alias}{rest}")))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, alias)| alias)
+    }
+
+    /// The zsh flags used to run the resume command.
+    fn resume_shell_flag(&self) -> &'static str {
+        let Some(raw) = self.resume_shell.as_deref() else {
+            return "-ic";
+        };
+        resume_shell_flag(raw).unwrap_or_else(|| {
+            eprintln!(
+                "cs-rs: ignoring unknown resume_shell {raw:?} (use interactive, login, or plain)"
+            );
+            "-ic"
+        })
+    }
+
+    fn stats_days(&self) -> usize {
+        self.stats_days
+            .filter(|days| *days > 0)
+            .unwrap_or(14)
+            .min(MAX_STATS_DAYS)
+    }
+
+    fn cost_rate_for_model(&self, model: &str) -> Option<f64> {
+        let model = model.to_lowercase();
+        let mut rates: HashMap<String, f64> = DEFAULT_COST_RATES
+            .iter()
+            .map(|(prefix, rate)| (prefix.to_string(), *rate))
+            .collect();
+        for (prefix, rate) in &self.cost_rates {
+            rates.insert(prefix.to_lowercase(), *rate);
+        }
+        rates
+            .into_iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rate)| rate)
+    }
+}
+
+fn resume_shell_flag(mode: &str) -> Option<&'static str> {
+    match mode.trim().to_ascii_lowercase().as_str() {
+        "interactive" | "-ic" | "-i" => Some("-ic"),
+        "login" | "-lc" | "-l" => Some("-lc"),
+        "plain" | "-c" => Some("-c"),
+        _ => None,
+    }
+}
+
+/// Adds a history entry to `seen`: the newest entry for a session supplies its
+/// prompt, project, and time, and older ones only fill in blanks.
+fn merge_history_session(seen: &mut HashMap<String, SessionInfo>, session: SessionInfo) {
+    let key = session.source.internal_key(&session.session_id);
+    let Some(existing) = seen.get_mut(&key) else {
+        seen.insert(key, session);
+        return;
+    };
+    if session.timestamp > existing.timestamp {
+        existing.timestamp = session.timestamp;
+        existing.display = session.display;
+        existing.project = session.project;
+    } else {
+        if existing.display.is_empty() && !session.display.is_empty() {
+            existing.display = session.display;
+        }
+        if existing.project.is_empty() && !session.project.is_empty() {
+            existing.project = session.project;
+        }
+    }
+    if existing.model.is_empty() {
+        existing.model = session.model;
+    }
+    if existing.reasoning_effort.is_empty() {
+        existing.reasoning_effort = session.reasoning_effort;
+    }
+    if existing.file_path.is_none() {
+        existing.file_path = session.file_path;
+    }
+}
+
+fn rotated_history_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut rotated: Vec<(u32, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let suffix = name.to_str()?.strip_prefix("history.jsonl.")?;
+            if !suffix.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            Some((suffix.parse().ok()?, entry.path()))
+        })
+        .filter(|(_, path)| path.is_file())
+        .collect();
+    rotated.sort_by_key(|(generation, _)| Reverse(*generation));
+    rotated.into_iter().map(|(_, path)| path).collect()
+}
+
+fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::load)
+}
+
+/// Sessions hidden everywhere via `~/.config/cs-rs/ignore`: one pattern per
+/// line, `#` for comments. Lines starting with `/`, `~`, or `*` are project
+/// path globs (`*` matches anything, including `/`) that also cover
+/// subdirectories; anything else is a session ID prefix.
+#[derive(Debug, Default)]
+struct IgnoreRules {
+    projects: Vec<Regex>,
+    id_prefixes: Vec<String>,
+}
+
+impl IgnoreRules {
+    fn file_path() -> PathBuf {
+        home_dir().join(".config").join("cs-rs").join("ignore")
+    }
+
+    fn load() -> Self {
+        fs::read_to_string(Self::file_path())
+            .map(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+
+    fn parse(raw: &str) -> Self {
+        let mut rules = Self::default();
+        for line in raw.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !(line.starts_with('/') || line.starts_with('~') || line.starts_with('*')) {
+                rules.id_prefixes.push(line.to_string());
+                continue;
+            }
+            let pattern = match line.strip_prefix('~') {
+                Some(rest) => format!("{}{rest}", home_dir().display()),
+                None => line.to_string(),
+            };
+            let pattern = pattern.trim_end_matches('/');
+            let body = pattern
+                .split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*");
+            if let Ok(re) = Regex::new(&format!("^{body}(?:/.*)?$")) {
+                rules.projects.push(re);
+            }
+        }
+        rules
+    }
+
+    fn is_empty(&self) -> bool {
+        self.projects.is_empty() && self.id_prefixes.is_empty()
+    }
+
+    fn matches(&self, session: &SessionInfo) -> bool {
+        self.id_prefixes
+            .iter()
+            .any(|prefix| session.session_id.starts_with(prefix.as_str()))
+            || self.projects.iter().any(|re| re.is_match(&session.project))
+    }
+}
+
+fn ignore_rules() -> &'static IgnoreRules {
+    IGNORE_RULES.get_or_init(IgnoreRules::load)
+}
+
+/// Zone used for every formatted timestamp and calendar day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DisplayZone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl DisplayZone {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        match raw.to_ascii_lowercase().as_str() {
+            "" | "local" => return Some(Self::Local),
+            "utc" | "z" | "gmt" => return Some(Self::Fixed(FixedOffset::east_opt(0)?)),
+            _ => {}
+        }
+        let rest = raw
+            .strip_prefix("UTC")
+            .or_else(|| raw.strip_prefix("utc"))
+            .unwrap_or(raw);
+        let (sign, rest) = match rest.chars().next()? {
+            '+' => (1, &rest[1..]),
+            '-' => (-1, &rest[1..]),
+            _ => return None,
+        };
+        let (hours, mins) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() == 4 => (rest.get(..2)?, rest.get(2..)?),
+            None => (rest, "0"),
+        };
+        let hours: i32 = hours.parse().ok()?;
+        let mins: i32 = mins.parse().ok()?;
+        if hours > 23 || mins > 59 {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + mins * 60)).map(Self::Fixed)
+    }
+
+    fn now(self) -> DateTime<FixedOffset> {
+        self.convert(Utc::now())
+    }
+
+    fn convert(self, utc: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Local => utc.with_timezone(&Local).fixed_offset(),
+            Self::Fixed(offset) => utc.with_timezone(&offset),
+        }
+    }
+
+    fn localize(self, naive: &chrono::NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Self::Local => Local
+                .from_local_datetime(naive)
+                .single()
+                .map(|dt| dt.fixed_offset()),
+            Self::Fixed(offset) => offset.from_local_datetime(naive).single(),
+        }
+    }
+}
+
+fn display_zone() -> DisplayZone {
+    *DISPLAY_ZONE.get_or_init(|| config().display_zone())
+}
+
+/// `ts_ms` in the configured display zone.
+fn display_time(ts_ms: i64) -> Option<DateTime<FixedOffset>> {
+    Utc.timestamp_millis_opt(ts_ms)
+        .single()
+        .map(|utc| display_zone().convert(utc))
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 enum SessionSource {
     Claudecode,
@@ -45,13 +414,52 @@ impl SessionSource {
         }
     }
 
+    /// Short label shown in list/TUI source columns; configurable via
+    /// `source_labels` or `nerd_font_glyphs` in the config file.
     fn list_label(&self) -> &'static str {
+        let config = config();
+        if let Some(label) = config
+            .source_labels
+            .get(self.cache_key())
+            .filter(|label| !label.trim().is_empty())
+        {
+            return label.as_str();
+        }
+        if config.nerd_font_glyphs {
+            return match self {
+                Self::Claudecode => "\u{f06a9}",
+                Self::Codex => "\u{e795}",
+            };
+        }
+        self.default_list_label()
+    }
+
+    /// Parses a `--source` value: `claude`/`claudecode`/`cc` or `codex`.
+    fn from_arg(raw: &str) -> Result<Self, String> {
+        match raw.trim().to_lowercase().as_str() {
+            "claude" | "claudecode" | "claude-code" | "cc" => Ok(Self::Claudecode),
+            "codex" => Ok(Self::Codex),
+            other => Err(format!("unknown source {other:?} (use claude or codex)")),
+        }
+    }
+
+    fn default_list_label(&self) -> &'static str {
         match self {
             Self::Claudecode => "cc",
             Self::Codex => "codex",
         }
     }
 
+    /// Terminal columns of the widest list label; nerd-font glyphs and CJK
+    /// labels can take two.
+    fn list_label_width() -> usize {
+        Self::all()
+            .iter()
+            .map(|source| source.list_label().width())
+            .max()
+            .unwrap_or(0)
+    }
+
     fn cache_key(&self) -> &'static str {
         match self {
             Self::Claudecode => "claudecode",
@@ -91,6 +499,19 @@ impl SessionSource {
         self.home_base().join("history.jsonl")
     }
 
+    /// Rotated copies of the history file (`history.jsonl.1`, ...), oldest
+    /// (highest number) first.
+    fn rotated_history_files(&self) -> Vec<PathBuf> {
+        rotated_history_files_in(&self.home_base())
+    }
+
+    /// The history file and its rotated copies, oldest first.
+    fn all_history_files(&self) -> Vec<PathBuf> {
+        let mut files = self.rotated_history_files();
+        files.push(self.history_file());
+        files
+    }
+
     fn projects_dir(&self) -> PathBuf {
         self.home_base().join("projects")
     }
@@ -103,11 +524,17 @@ impl SessionSource {
         self.home_base().join("archived_sessions")
     }
 
+    /// `~/.claude` or `~/.codex`, unless `CS_RS_CLAUDE_DIR` / `CS_RS_CODEX_DIR`
+    /// (or `CS_RS_DATA_DIR`, see `data_home`) point elsewhere.
     fn home_base(&self) -> PathBuf {
-        match self {
-            Self::Claudecode => home_dir().join(".claude"),
-            Self::Codex => home_dir().join(".codex"),
-        }
+        let (var, dir) = match self {
+            Self::Claudecode => ("CS_RS_CLAUDE_DIR", ".claude"),
+            Self::Codex => ("CS_RS_CODEX_DIR", ".codex"),
+        };
+        env::var_os(var)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_home().join(dir))
     }
 
     fn internal_key(&self, session_id: &str) -> String {
@@ -133,6 +560,45 @@ struct SessionCache {
     version: u32,
     histories: HashMap<String, CachedHistory>,
     codex_sessions: HashMap<String, CachedCodexSession>,
+    /// User-assigned labels keyed by `SessionSource::internal_key`.
+    #[serde(default)]
+    tags: HashMap<String, Vec<String>>,
+    /// User/assistant message counts and durations keyed by
+    /// `SessionSource::internal_key`.
+    #[serde(default)]
+    message_counts: HashMap<String, CachedMessageCount>,
+    /// Sessions removed with `cs delete`; hidden even though history still
+    /// lists them.
+    #[serde(default)]
+    deleted: Vec<String>,
+    /// Sessions pinned to the top of the TUI list.
+    #[serde(default)]
+    pinned: Vec<String>,
+    /// When each session was last resumed through cs-rs (epoch ms), keyed by
+    /// `SessionSource::internal_key`; drives the `resumed` sort.
+    #[serde(default)]
+    resumed: HashMap<String, i64>,
+}
+
+/// Bumped whenever `CachedMessageCount` is computed differently, so stale
+/// entries are recounted.
+const MESSAGE_COUNT_VERSION: u32 = 3;
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct CachedMessageCount {
+    /// `MESSAGE_COUNT_VERSION` at the time of counting; 0 in older entries.
+    #[serde(default)]
+    version: u32,
+    file_size: u64,
+    file_modified_ms: i64,
+    count: usize,
+    /// Last minus first message time; `None` with fewer than two timestamps.
+    #[serde(default)]
+    duration_ms: Option<i64>,
+    /// The user share of `count`; `None` only in entries from before it was
+    /// counted, which the version check recomputes.
+    #[serde(default)]
+    user_turns: Option<usize>,
 }
 
 #[derive(Clone, Default, Deserialize, Serialize)]
@@ -153,6 +619,8 @@ struct CachedCodexSession {
     timestamp_ms: Option<i64>,
     model: Option<String>,
     reasoning_effort: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
 }
 
 #[derive(Clone)]
@@ -169,12 +637,18 @@ struct StatsSourceRow {
     first_session_date: String,
     top_models: Vec<(String, u64)>,
     daily_sessions: Vec<(String, u64)>,
+    estimated_cost_usd: Option<f64>,
+    /// User prompts and assistant replies across the source's session files;
+    /// `None` unless `--turns` asked for them.
+    turns: Option<(u64, u64)>,
 }
 
 struct StatsReport {
     total_sessions: u64,
     total_history_entries: u64,
     last_computed_date: String,
+    /// Width of the daily activity window ending on `last_computed_date`.
+    days: usize,
     sources: Vec<StatsSourceRow>,
 }
 
@@ -186,8 +660,18 @@ impl SessionInfo {
     fn list_id_tail(&self) -> String {
         session_id_hex_tail(&self.session_id, 5)
     }
+
+    /// Whether the session file lives in `archived_sessions` (see `cs archive`).
+    fn is_archived(&self) -> bool {
+        self.file_path
+            .as_deref()
+            .is_some_and(|path| Path::new(path).starts_with(self.source.archived_sessions_dir()))
+    }
 }
 
+/// (timestamp ms, prompt text) pairs read from a history file.
+type HistoryPrompts = Vec<(i64, String)>;
+
 #[derive(Deserialize)]
 struct HistoryEntry {
     #[serde(alias = "sessionId")]
@@ -219,6 +703,8 @@ struct CodexSessionFileInfo {
     timestamp_ms: Option<i64>,
     model: Option<String>,
     reasoning_effort: Option<String>,
+    /// Session title or opening instruction, used when history has no display.
+    title: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -241,7 +727,7 @@ struct RawMessage {
 struct Message {
     msg_type: String,
     _uuid: String,
-    _timestamp: String,
+    timestamp: String,
     is_api_error: bool,
     _session_id: String,
     message: Value,
@@ -252,7 +738,7 @@ impl From<RawMessage> for Message {
         Self {
             msg_type: raw.msg_type.unwrap_or_default(),
             _uuid: raw.uuid,
-            _timestamp: raw.timestamp,
+            timestamp: raw.timestamp,
             is_api_error: raw.is_api_error,
             _session_id: raw.session_id,
             message: raw.message,
@@ -261,6 +747,13 @@ impl From<RawMessage> for Message {
 }
 
 impl Message {
+    /// A user prompt or assistant reply with prose; tool calls and tool
+    /// results (including Codex `function_call` entries) have none and don't
+    /// count.
+    fn is_conversation_turn(&self) -> bool {
+        matches!(self.msg_type.as_str(), "user" | "assistant") && !self.text().is_empty()
+    }
+
     fn role(&self) -> &str {
         self.message
             .get("role")
@@ -298,6 +791,59 @@ impl Message {
     }
 }
 
+/// Files at most this size are read to check for whitespace-only content.
+const BLANK_FILE_PROBE_BYTES: u64 = 4096;
+
+/// Whether `path` is a file with something in it. Empty and whitespace-only
+/// transcripts (e.g. a session abandoned before the first message) have
+/// nothing to view or resume, so they are left out of listings.
+fn session_file_has_content(path: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() || metadata.len() == 0 {
+        return false;
+    }
+    if metadata.len() > BLANK_FILE_PROBE_BYTES {
+        return true;
+    }
+    fs::read(path).is_ok_and(|bytes| !bytes.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Top-level `type`s that only appear in Codex rollout files.
+const CODEX_LINE_TYPES: &[&str] = &[
+    "session_meta",
+    "response_item",
+    "turn_context",
+    "event_msg",
+    "compacted",
+];
+
+/// Claude Code or Codex, judged from the `type` of the first JSON lines of a
+/// transcript: Codex writes `session_meta`/`response_item` envelopes, Claude
+/// Code writes `user`/`assistant` entries with a `message`, plus its own
+/// bookkeeping types. `None` for empty, unreadable, or unrecognized files.
+fn sniff_transcript_source(path: &Path) -> Option<SessionSource> {
+    let reader = open_session_file(path).ok()?;
+    for line in reader.lines().map_while(Result::ok).take(20) {
+        let Ok(value) = serde_json::from_str::<Value>(line.trim()) else {
+            continue;
+        };
+        let Some(line_type) = value.get("type").and_then(Value::as_str) else {
+            continue;
+        };
+        if CODEX_LINE_TYPES.contains(&line_type) {
+            return Some(SessionSource::Codex);
+        }
+        let claude_entry =
+            matches!(line_type, "user" | "assistant") && value.get("message").is_some();
+        if claude_entry || line_type == "summary" || INTERNAL_TYPES.contains(&line_type) {
+            return Some(SessionSource::Claudecode);
+        }
+    }
+    None
+}
+
 fn parse_codex_message(line: &str) -> Option<Message> {
     let value: Value = serde_json::from_str(line).ok()?;
     if value.get("type").and_then(Value::as_str) != Some("response_item") {
@@ -305,25 +851,68 @@ fn parse_codex_message(line: &str) -> Option<Message> {
     }
 
     let payload = value.get("payload")?;
-    if payload.get("type").and_then(Value::as_str)? != "message" {
-        return None;
-    }
-
-    let role = payload
-        .get("role")
-        .and_then(Value::as_str)
-        .unwrap_or("assistant");
-    let msg_type = match role {
-        "user" => "user",
-        "assistant" => "assistant",
-        "developer" => "assistant",
-        _ => "assistant",
+    let (msg_type, message) = match payload.get("type").and_then(Value::as_str)? {
+        "message" => {
+            let role = payload
+                .get("role")
+                .and_then(Value::as_str)
+                .unwrap_or("assistant");
+            let msg_type = match role {
+                "user" => "user",
+                "assistant" => "assistant",
+                "developer" => "assistant",
+                _ => "assistant",
+            };
+            (
+                msg_type,
+                json!({
+                    "role": role,
+                    "content": payload.get("content").unwrap_or(&Value::Null),
+                    "model": payload.get("model").unwrap_or(&Value::Null),
+                }),
+            )
+        }
+        // Codex tool calls are mapped onto Claude-style tool_use/tool_result
+        // blocks so rendering can treat both sources the same way.
+        "function_call" | "custom_tool_call" => {
+            let input = match payload.get("arguments").or_else(|| payload.get("input")) {
+                Some(Value::String(raw)) => {
+                    serde_json::from_str(raw).unwrap_or_else(|_| json!({ "input": raw }))
+                }
+                Some(other) => other.clone(),
+                None => Value::Null,
+            };
+            (
+                "assistant",
+                json!({
+                    "role": "assistant",
+                    "content": [{
+                        "type": "tool_use",
+                        "id": payload.get("call_id").unwrap_or(&Value::Null),
+                        "name": payload.get("name").unwrap_or(&Value::Null),
+                        "input": input,
+                    }],
+                }),
+            )
+        }
+        "function_call_output" | "custom_tool_call_output" => (
+            "user",
+            json!({
+                "role": "user",
+                "content": [{
+                    "type": "tool_result",
+                    "tool_use_id": payload.get("call_id").unwrap_or(&Value::Null),
+                    "content": payload.get("output").unwrap_or(&Value::Null),
+                }],
+            }),
+        ),
+        _ => return None,
     };
 
     Some(Message {
         msg_type: msg_type.to_string(),
         _uuid: String::new(),
-        _timestamp: value
+        timestamp: value
             .get("timestamp")
             .and_then(Value::as_str)
             .unwrap_or("")
@@ -337,11 +926,7 @@ fn parse_codex_message(line: &str) -> Option<Message> {
             .or_else(|| value.get("session_id").and_then(Value::as_str))
             .unwrap_or_default()
             .to_string(),
-        message: json!({
-            "role": role,
-            "content": payload.get("content").unwrap_or(&Value::Null),
-            "model": payload.get("model").unwrap_or(&Value::Null),
-        }),
+        message,
     })
 }
 
@@ -359,6 +944,33 @@ fn block_text(block: &Value) -> Option<String> {
         .map(str::to_string)
 }
 
+fn tool_result_preview(block: &Value) -> Option<String> {
+    let text = match block.get("content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str).map(str::to_string))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(Value::Object(object)) => object
+            .get("output")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    };
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let preview = truncate(&text, 160);
+    if block.get("is_error").and_then(Value::as_bool) == Some(true) {
+        Some(format!("error: {preview}"))
+    } else {
+        Some(preview)
+    }
+}
+
 fn normalize_timestamp(ts: Option<i64>) -> i64 {
     match ts {
         Some(raw) if raw > 0 && raw < 1_000_000_000_000 => raw * 1000,
@@ -410,6 +1022,17 @@ fn codex_effort_candidate(value: &str) -> Option<String> {
     }
 }
 
+fn codex_title_candidate(text: &str) -> Option<String> {
+    // Skip injected context blocks such as <environment_context> and
+    // <user_instructions>; they make poor titles.
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('<') || text.starts_with("# AGENTS.md") {
+        return None;
+    }
+    let first_line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    Some(first_line.chars().take(200).collect())
+}
+
 fn codex_entry_session_id(value: &Value) -> Option<&str> {
     value
         .get("sessionId")
@@ -440,6 +1063,85 @@ fn format_with_commas(n: u64) -> String {
     output.chars().rev().collect()
 }
 
+/// Plain and compressed transcript suffixes; compressed files are read by
+/// piping through the matching `gzip`/`zstd` binary.
+const SESSION_FILE_SUFFIXES: &[&str] = &[".jsonl", ".jsonl.gz", ".jsonl.zst"];
+
+fn session_file_stem(name: &str) -> Option<&str> {
+    SESSION_FILE_SUFFIXES
+        .iter()
+        .rev()
+        .find_map(|suffix| name.strip_suffix(suffix))
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(session_file_stem)
+        .is_some()
+}
+
+fn open_session_file(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let decompressor = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => "gzip",
+        Some("zst") => "zstd",
+        _ => return Ok(Box::new(BufReader::new(File::open(path)?))),
+    };
+    let mut child = Command::new(decompressor)
+        .arg("-dc")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let Some(stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(io::Error::other(format!("{decompressor} has no output")));
+    };
+    Ok(Box::new(BufReader::new(DecompressedReader {
+        child,
+        stdout,
+        decompressor,
+        path: path.to_path_buf(),
+    })))
+}
+
+/// Streams a decompressor's output, so large archived transcripts are read
+/// line by line like plain ones instead of being buffered whole. A failed
+/// exit surfaces as a read error at end of stream.
+struct DecompressedReader {
+    child: Child,
+    stdout: ChildStdout,
+    decompressor: &'static str,
+    path: PathBuf,
+}
+
+impl io::Read for DecompressedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read == 0 && !buf.is_empty() && !self.child.wait()?.success() {
+            return Err(io::Error::other(format!(
+                "{} failed to decompress {}",
+                self.decompressor,
+                self.path.display()
+            )));
+        }
+        Ok(read)
+    }
+}
+
+impl Drop for DecompressedReader {
+    fn drop(&mut self) {
+        // Readers that stop early (`--head`, search hits) leave the child
+        // mid-stream; don't leave it running or unreaped.
+        if matches!(self.child.try_wait(), Ok(None)) {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
 fn human_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
     if size == 0 {
@@ -462,13 +1164,25 @@ fn human_file_size(size: u64) -> String {
     }
 }
 
-fn file_size_for_session(file_path: &Option<String>) -> (String, bool) {
-    match file_path
-        .as_deref()
-        .and_then(|path| fs::metadata(path).ok())
-        .map(|m| m.len())
-    {
-        Some(size) => (human_file_size(size), size > 1_048_576),
+/// Session file sizes by internal key, for the TUI's size column; sessions
+/// without a readable file are left out.
+fn build_file_size_map(sessions: &[SessionInfo]) -> HashMap<String, u64> {
+    sessions
+        .iter()
+        .filter_map(|session| {
+            let size = fs::metadata(session.file_path.as_deref()?).ok()?.len();
+            Some((session.source.internal_key(&session.session_id), size))
+        })
+        .collect()
+}
+
+/// The size column's text, and whether it crosses the large-file threshold.
+fn file_size_label(size: Option<u64>) -> (String, bool) {
+    match size {
+        Some(size) => (
+            human_file_size(size),
+            size > config().large_file_threshold_bytes(),
+        ),
         None => ("—".to_string(), false),
     }
 }
@@ -485,8 +1199,8 @@ fn list_time_ms_for_session(session: &SessionInfo) -> i64 {
 }
 
 fn list_time(session_ts_ms: i64) -> String {
-    let now = Local::now();
-    let when = match Local.timestamp_millis_opt(session_ts_ms).single() {
+    let now = display_zone().now();
+    let when = match display_time(session_ts_ms) {
         Some(ts) => ts,
         None => return "—".to_string(),
     };
@@ -528,6 +1242,50 @@ fn build_list_time_ms_map(sessions: &[SessionInfo]) -> HashMap<String, i64> {
         .collect()
 }
 
+/// Upper bound on search worker threads; past this, disk reads dominate.
+const SEARCH_MAX_WORKERS: usize = 8;
+/// Sessions handed to each worker per search batch.
+const SEARCH_BATCH_PER_WORKER: usize = 4;
+
+/// Maps `f` over `items` on up to `workers` scoped threads, returning results
+/// in input order. Workers pull the next index from a shared counter, so a
+/// few large sessions don't leave the other threads idle.
+fn parallel_map<T, R, F>(items: &[T], workers: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if workers <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut slots: Vec<Option<R>> = std::iter::repeat_with(|| None).take(items.len()).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(idx) else {
+                            break;
+                        };
+                        done.push((idx, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (idx, result) in handle.join().expect("search worker panicked") {
+                slots[idx] = Some(result);
+            }
+        }
+    });
+    slots.into_iter().flatten().collect()
+}
+
 fn build_list_time_str_map(ms_map: &HashMap<String, i64>) -> HashMap<String, String> {
     ms_map
         .iter()
@@ -541,6 +1299,19 @@ struct SessionStore {
     cache: SessionCache,
     cache_dirty: bool,
     search_text_cache: HashMap<String, SearchTextCacheEntry>,
+    keep_duplicates: bool,
+    /// Per-source history entries seen by the last `load`, for `cs doctor`.
+    load_counts: HashMap<SessionSource, LoadCounts>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct LoadCounts {
+    /// Entries left after collapsing duplicates and dropping deleted sessions.
+    in_history: usize,
+    /// Entries whose transcript could not be found on disk.
+    missing_file: usize,
+    /// Loaded sessions hidden by the ignore file.
+    ignored: usize,
 }
 
 impl SessionStore {
@@ -551,15 +1322,29 @@ impl SessionStore {
             cache: Self::load_cache(),
             cache_dirty: false,
             search_text_cache: HashMap::new(),
+            keep_duplicates: false,
+            load_counts: HashMap::new(),
         }
     }
 
+    /// Claude Code's project directory name: every character other than an
+    /// ASCII letter or digit becomes `-` (one per UTF-16 unit, as in JS), so
+    /// `/Users/me/my_app.v2` -> `-Users-me-my-app-v2`.
     fn encode_path(path: &str) -> String {
-        path.replace(['/', '.'], "-")
+        path.chars()
+            .flat_map(|ch| {
+                let (ch, count) = if ch.is_ascii_alphanumeric() {
+                    (ch, 1)
+                } else {
+                    ('-', ch.len_utf16())
+                };
+                std::iter::repeat_n(ch, count)
+            })
+            .collect()
     }
 
     fn cache_file_path() -> PathBuf {
-        home_dir()
+        data_home()
             .join(".local")
             .join("state")
             .join("cs-rs")
@@ -568,7 +1353,7 @@ impl SessionStore {
 
     fn load_cache() -> SessionCache {
         let path = Self::cache_file_path();
-        let raw = match fs::read_to_string(path) {
+        let raw = match fs::read_to_string(&path) {
             Ok(raw) => raw,
             Err(_) => {
                 return SessionCache {
@@ -586,10 +1371,22 @@ impl SessionStore {
                 }
                 cache
             }
-            Err(_) => SessionCache {
-                version: 1,
-                ..SessionCache::default()
-            },
+            Err(err) => {
+                // Keep the unreadable cache around for inspection instead of
+                // silently overwriting it on the next save.
+                let backup = path.with_extension("json.corrupt");
+                match fs::rename(&path, &backup) {
+                    Ok(()) => eprintln!(
+                        "cs-rs: session cache was unreadable ({err}); moved to {} and rebuilding",
+                        backup.display()
+                    ),
+                    Err(_) => eprintln!("cs-rs: session cache was unreadable ({err}); rebuilding"),
+                }
+                SessionCache {
+                    version: 1,
+                    ..SessionCache::default()
+                }
+            }
         }
     }
 
@@ -619,11 +1416,79 @@ impl SessionStore {
         let _ = fs::rename(tmp_path, cache_path);
     }
 
-    fn metadata_modified_ms(metadata: &fs::Metadata) -> Option<i64> {
-        metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    fn session_tags(&self, session: &SessionInfo) -> &[String] {
+        self.cache
+            .tags
+            .get(&session.source.internal_key(&session.session_id))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn toggle_session_tag(&mut self, session: &SessionInfo, tag: &str) {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return;
+        }
+        let key = session.source.internal_key(&session.session_id);
+        let tags = self.cache.tags.entry(key.clone()).or_default();
+        match tags.iter().position(|t| t.eq_ignore_ascii_case(tag)) {
+            Some(pos) => {
+                tags.remove(pos);
+            }
+            None => tags.push(tag.to_string()),
+        }
+        if tags.is_empty() {
+            self.cache.tags.remove(&key);
+        }
+        self.cache_dirty = true;
+        self.save_cache_if_dirty();
+    }
+
+    fn is_pinned(&self, session: &SessionInfo) -> bool {
+        self.cache
+            .pinned
+            .contains(&session.source.internal_key(&session.session_id))
+    }
+
+    fn toggle_pinned(&mut self, session: &SessionInfo) {
+        let key = session.source.internal_key(&session.session_id);
+        match self.cache.pinned.iter().position(|pinned| *pinned == key) {
+            Some(pos) => {
+                self.cache.pinned.remove(pos);
+            }
+            None => self.cache.pinned.push(key),
+        }
+        self.cache_dirty = true;
+        self.save_cache_if_dirty();
+    }
+
+    /// Reorders `filter_sessions` output (already newest-first) for `--sort`.
+    fn sort_for_list(&self, sessions: &mut [SessionInfo], sort: SessionSort) {
+        if sort != SessionSort::Recent {
+            let times = build_list_time_ms_map(sessions);
+            sort_sessions(sessions, sort, &times, &self.cache.resumed);
+        }
+    }
+
+    fn record_resume(&mut self, session: &SessionInfo) {
+        self.cache.resumed.insert(
+            session.source.internal_key(&session.session_id),
+            Utc::now().timestamp_millis(),
+        );
+        self.cache_dirty = true;
+        self.save_cache_if_dirty();
+    }
+
+    /// Moves pinned sessions to the front, keeping the order within each group.
+    fn pinned_first(&self, sessions: &mut [SessionInfo]) {
+        sessions.sort_by_key(|session| !self.is_pinned(session));
+    }
+
+    fn metadata_modified_ms(metadata: &fs::Metadata) -> Option<i64> {
+        metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
             .map(|d| d.as_millis() as i64)
     }
 
@@ -679,49 +1544,127 @@ impl SessionStore {
                 entry.text
             };
 
-            let project = entry.project;
-            let timestamp = normalize_timestamp(entry.timestamp.or(entry.ts));
+            merge_history_session(
+                seen,
+                SessionInfo {
+                    source,
+                    session_id,
+                    display,
+                    project: entry.project,
+                    timestamp: normalize_timestamp(entry.timestamp.or(entry.ts)),
+                    model: String::new(),
+                    reasoning_effort: String::new(),
+                    file_path: None,
+                },
+            );
+        }
+        parsed_lines
+    }
 
-            let key = source.internal_key(&session_id);
-            match seen.get_mut(&key) {
-                Some(existing) => {
-                    if timestamp > existing.timestamp {
-                        existing.timestamp = timestamp;
-                        existing.display = display.clone();
-                        existing.project = project.clone();
-                    } else {
-                        if existing.display.is_empty() && !display.is_empty() {
-                            existing.display = display.clone();
-                        }
-                        if existing.project.is_empty() && !project.is_empty() {
-                            existing.project = project.clone();
-                        }
-                    }
-                }
-                None => {
-                    seen.insert(
-                        source.internal_key(&session_id),
-                        SessionInfo {
-                            source,
-                            session_id,
-                            display,
-                            project,
-                            timestamp,
-                            model: String::new(),
-                            reasoning_effort: String::new(),
-                            file_path: None,
-                        },
-                    );
+    /// Raw prompts recorded in each source's history file for the session
+    /// matching `session_id` (exact or unique prefix), oldest first. Works even
+    /// when the transcript file is gone.
+    fn history_entries_for_session(
+        session_id: &str,
+    ) -> Result<(SessionSource, String, HistoryPrompts)> {
+        let mut by_session: BTreeMap<(&'static str, String), (SessionSource, HistoryPrompts)> =
+            BTreeMap::new();
+        for source in SessionSource::all() {
+            let files = source
+                .all_history_files()
+                .into_iter()
+                .filter_map(|path| File::open(path).ok());
+            for line in files.flat_map(|file| BufReader::new(file).lines().map_while(Result::ok)) {
+                let Ok(entry) = serde_json::from_str::<HistoryEntry>(line.trim()) else {
+                    continue;
+                };
+                let Some(id) = entry.session_id.or(entry.session_id_legacy) else {
+                    continue;
+                };
+                if !id.starts_with(session_id) {
+                    continue;
                 }
+                let display = if entry.display.is_empty() {
+                    entry.text
+                } else {
+                    entry.display
+                };
+                by_session
+                    .entry((source.cache_key(), id))
+                    .or_insert_with(|| (*source, Vec::new()))
+                    .1
+                    .push((normalize_timestamp(entry.timestamp.or(entry.ts)), display));
             }
         }
-        parsed_lines
+
+        let exact: Vec<_> = by_session
+            .keys()
+            .filter(|(_, id)| id == session_id)
+            .cloned()
+            .collect();
+        let key = match (exact.len(), by_session.len()) {
+            (1, _) => exact[0].clone(),
+            (0, 1) => by_session.keys().next().cloned().unwrap_or_default(),
+            (0, 0) => {
+                return Err(anyhow!(
+                    "No history entries found for session: {session_id}"
+                ))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Session ID is ambiguous in history ({} matches): {session_id}",
+                    by_session.len()
+                ))
+            }
+        };
+        let Some((source, mut entries)) = by_session.remove(&key) else {
+            return Err(anyhow!(
+                "No history entries found for session: {session_id}"
+            ));
+        };
+        entries.sort_by_key(|(ts, _)| *ts);
+        Ok((source, key.1, entries))
     }
 
+    /// Sessions from the source's history file, merged with any rotated
+    /// copies so prompts from before a rotation stay listed. Each file is
+    /// cached separately (rotated ones under `<source>:<file name>`).
     fn load_sessions_for_source(&mut self, source: SessionSource) -> HashMap<String, SessionInfo> {
+        let mut seen = self.load_history_file(
+            source,
+            &source.history_file(),
+            source.cache_key().to_string(),
+        );
+        let rotated_prefix = format!("{}:", source.cache_key());
+        let mut rotated_keys = Vec::new();
+        for path in source.rotated_history_files() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let cache_key = format!("{rotated_prefix}{name}");
+            rotated_keys.push(cache_key.clone());
+            for session in self
+                .load_history_file(source, &path, cache_key)
+                .into_values()
+            {
+                merge_history_session(&mut seen, session);
+            }
+        }
+        let before = self.cache.histories.len();
+        self.cache
+            .histories
+            .retain(|key, _| !key.starts_with(&rotated_prefix) || rotated_keys.contains(key));
+        if self.cache.histories.len() != before {
+            self.cache_dirty = true;
+        }
+        seen
+    }
+
+    fn load_history_file(
+        &mut self,
+        source: SessionSource,
+        history_path: &Path,
+        cache_key: String,
+    ) -> HashMap<String, SessionInfo> {
         let mut seen: HashMap<String, SessionInfo> = HashMap::new();
-        let history_path = source.history_file();
-        let cache_key = source.cache_key().to_string();
         if !history_path.exists() {
             if let Some(cached) = self.cache.histories.get(&cache_key) {
                 for session in &cached.sessions {
@@ -731,7 +1674,7 @@ impl SessionStore {
             return seen;
         }
 
-        let metadata = match fs::metadata(&history_path) {
+        let metadata = match fs::metadata(history_path) {
             Ok(metadata) => metadata,
             Err(_) => {
                 if let Some(cached) = self.cache.histories.get(&cache_key) {
@@ -764,7 +1707,7 @@ impl SessionStore {
                 }
                 let appended = self.parse_history_lines_into(
                     source,
-                    &history_path,
+                    history_path,
                     cached.file_size,
                     &mut seen,
                 );
@@ -782,7 +1725,7 @@ impl SessionStore {
             }
         }
 
-        let line_count = self.parse_history_lines_into(source, &history_path, 0, &mut seen);
+        let line_count = self.parse_history_lines_into(source, history_path, 0, &mut seen);
         self.cache.histories.insert(
             cache_key,
             CachedHistory {
@@ -804,8 +1747,7 @@ impl SessionStore {
             .file_path
             .as_deref()
             .map(Path::new)
-            .map(Path::is_file)
-            .unwrap_or(false)
+            .is_some_and(session_file_has_content)
     }
 
     fn codex_session_file_changed(&self, session_id: &str, path: &Path) -> bool {
@@ -829,20 +1771,22 @@ impl SessionStore {
         }
     }
 
+    /// Model and reasoning effort of the newest session of `source` that
+    /// recorded a model, optionally skipping one session.
     fn most_recent_model_for_source(
         &self,
         source: SessionSource,
-        exclude_session_id: &str,
-    ) -> Option<String> {
+        exclude_session_id: Option<&str>,
+    ) -> Option<(String, String)> {
         self.sessions
             .values()
             .filter(|session| {
                 session.source == source
-                    && session.session_id != exclude_session_id
+                    && exclude_session_id != Some(session.session_id.as_str())
                     && !session.model.trim().is_empty()
             })
             .max_by_key(|session| session.timestamp)
-            .map(|session| session.model.clone())
+            .map(|session| (session.model.clone(), session.reasoning_effort.clone()))
     }
 
     fn update_history_cache_session(&mut self, session: &SessionInfo) {
@@ -897,6 +1841,11 @@ impl SessionStore {
                 session.reasoning_effort = reasoning_effort.to_string();
             }
         }
+        if session.display.is_empty() {
+            if let Some(title) = cached.title.as_deref() {
+                session.display = title.to_string();
+            }
+        }
     }
 
     fn update_codex_cache(
@@ -936,6 +1885,9 @@ impl SessionStore {
             if let Some(reasoning_effort) = info.reasoning_effort.as_ref() {
                 entry.reasoning_effort = Some(reasoning_effort.clone());
             }
+            if let Some(title) = info.title.as_ref() {
+                entry.title = Some(title.clone());
+            }
         }
         self.cache
             .codex_sessions
@@ -959,15 +1911,10 @@ impl SessionStore {
         for session in seen.values_mut() {
             match session.source {
                 SessionSource::Claudecode => {
-                    if !session.project.is_empty() {
-                        let candidate = session
-                            .source
-                            .projects_dir()
-                            .join(Self::encode_path(&session.project))
-                            .join(format!("{}.jsonl", session.session_id));
-                        if candidate.exists() {
-                            session.file_path = Some(candidate.to_string_lossy().to_string());
-                        }
+                    if let Some(path) =
+                        Self::claudecode_session_file(&session.project, &session.session_id)
+                    {
+                        session.file_path = Some(path.to_string_lossy().to_string());
                     }
                 }
                 SessionSource::Codex => {
@@ -990,7 +1937,9 @@ impl SessionStore {
                                 session.file_path = Some(path.to_string_lossy().to_string());
                             }
 
-                            let needs_meta = session.project.is_empty() || session.timestamp == 0;
+                            let needs_meta = session.project.is_empty()
+                                || session.timestamp == 0
+                                || session.display.is_empty();
                             if needs_meta {
                                 if let Some(info) = self
                                     .codex_file_info_from_session_file(path, &session.session_id)
@@ -1017,6 +1966,11 @@ impl SessionStore {
                                             session.reasoning_effort = reasoning_effort.to_string();
                                         }
                                     }
+                                    if session.display.is_empty() {
+                                        if let Some(title) = info.title.as_deref() {
+                                            session.display = title.to_string();
+                                        }
+                                    }
                                     self.update_codex_cache(&session.session_id, path, Some(&info));
                                 } else {
                                     self.update_codex_cache(&session.session_id, path, None);
@@ -1034,15 +1988,146 @@ impl SessionStore {
             }
         }
 
-        seen.retain(|_, session| {
+        if !self.keep_duplicates {
+            Self::collapse_duplicate_sessions(&mut seen);
+        }
+        self.load_counts.clear();
+        for (key, session) in &seen {
+            if self.cache.deleted.contains(key) {
+                continue;
+            }
+            let counts = self.load_counts.entry(session.source).or_default();
+            counts.in_history += 1;
+            if session.file_path.is_none() {
+                counts.missing_file += 1;
+            }
+        }
+        seen.retain(|key, session| {
             !(session.display.is_empty() && session.timestamp == 0)
                 && Self::is_resumable_session(session)
+                && !self.cache.deleted.contains(key)
         });
+        let ignore = ignore_rules();
+        if !ignore.is_empty() {
+            let load_counts = &mut self.load_counts;
+            seen.retain(|_, session| {
+                let ignored = ignore.matches(session);
+                if ignored {
+                    load_counts.entry(session.source).or_default().ignored += 1;
+                }
+                !ignored
+            });
+        }
         self.sessions = seen;
         self.loaded = true;
         self.save_cache_if_dirty();
     }
 
+    /// Collapses entries that are very likely the same conversation recorded
+    /// under slightly different IDs (e.g. after a Codex session format
+    /// migration): same source and project, same opening prompt, and started
+    /// within a few minutes of each other, or simply pointing at the same
+    /// session file. Entries backed by distinct files are never merged.
+    fn collapse_duplicate_sessions(seen: &mut HashMap<String, SessionInfo>) {
+        const WINDOW_MS: i64 = 10 * 60 * 1000;
+
+        let mut by_file: HashMap<(SessionSource, String), Vec<String>> = HashMap::new();
+        let mut by_prompt: HashMap<(SessionSource, String, String), Vec<(String, i64)>> =
+            HashMap::new();
+        for (key, session) in seen.iter() {
+            if let Some(path) = session.file_path.as_ref() {
+                by_file
+                    .entry((session.source, path.clone()))
+                    .or_default()
+                    .push(key.clone());
+            }
+            if session.display.trim().is_empty() || session.display == session.project {
+                continue;
+            }
+            let prompt = session
+                .display
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+                .chars()
+                .take(80)
+                .collect::<String>();
+            by_prompt
+                .entry((session.source, session.project.clone(), prompt))
+                .or_default()
+                .push((key.clone(), session.timestamp));
+        }
+
+        for cluster in by_file.into_values() {
+            Self::merge_duplicate_cluster(seen, &cluster);
+        }
+
+        for mut members in by_prompt.into_values().filter(|members| members.len() > 1) {
+            members.sort_by_key(|(_, ts)| *ts);
+            let mut cluster: Vec<String> = Vec::new();
+            let mut cluster_end = i64::MIN;
+            for (key, ts) in members {
+                if !cluster.is_empty() && ts - cluster_end > WINDOW_MS {
+                    Self::merge_duplicate_cluster(seen, &cluster);
+                    cluster.clear();
+                }
+                cluster.push(key);
+                cluster_end = ts;
+            }
+            Self::merge_duplicate_cluster(seen, &cluster);
+        }
+    }
+
+    fn merge_duplicate_cluster(seen: &mut HashMap<String, SessionInfo>, cluster: &[String]) {
+        let mut members: Vec<SessionInfo> = cluster
+            .iter()
+            .filter_map(|key| seen.get(key).cloned())
+            .collect();
+        if members.len() < 2 {
+            return;
+        }
+        members.sort_by_key(|s| Reverse(s.timestamp));
+
+        let mut kept_files: HashMap<String, String> = HashMap::new();
+        for session in members.iter().filter(|s| Self::is_resumable_session(s)) {
+            if let Some(path) = session.file_path.as_ref() {
+                kept_files
+                    .entry(path.clone())
+                    .or_insert_with(|| session.source.internal_key(&session.session_id));
+            }
+        }
+        let Some(primary) = members
+            .iter()
+            .find(|s| Self::is_resumable_session(s))
+            .map(|s| s.source.internal_key(&s.session_id))
+        else {
+            return;
+        };
+
+        for session in &members {
+            let key = session.source.internal_key(&session.session_id);
+            if kept_files.values().any(|kept| *kept == key) {
+                continue;
+            }
+            let target_key = session
+                .file_path
+                .as_ref()
+                .and_then(|path| kept_files.get(path))
+                .unwrap_or(&primary)
+                .clone();
+            seen.remove(&key);
+            if let Some(target) = seen.get_mut(&target_key) {
+                if target.model.is_empty() {
+                    target.model = session.model.clone();
+                }
+                if target.reasoning_effort.is_empty() {
+                    target.reasoning_effort = session.reasoning_effort.clone();
+                }
+            }
+        }
+    }
+
     fn enrich_session_for_access(&mut self, source: SessionSource, session_id: &str) {
         self.load();
         let key = source.internal_key(session_id);
@@ -1093,6 +2178,12 @@ impl SessionStore {
                             session.timestamp = info.timestamp_ms.unwrap_or(0);
                             session_changed = true;
                         }
+                        if session.display.is_empty() || session.display == session.project {
+                            if let Some(title) = info.title.as_deref() {
+                                session.display = title.to_string();
+                                session_changed = true;
+                            }
+                        }
                         if let Some(model) = info.model.as_deref() {
                             if (file_changed || session.model.is_empty()) && session.model != model
                             {
@@ -1139,7 +2230,7 @@ impl SessionStore {
     fn all(&mut self) -> Vec<SessionInfo> {
         self.load();
         let mut out: Vec<_> = self.sessions.values().cloned().collect();
-        out.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        out.sort_by_key(|s| Reverse(s.timestamp));
         out
     }
 
@@ -1152,13 +2243,29 @@ impl SessionStore {
             .get(&source.internal_key(session_id))
             .cloned()?;
         if session.model.trim().is_empty() {
-            if let Some(model) = self.most_recent_model_for_source(source, &session.session_id) {
+            if let Some((model, _)) =
+                self.most_recent_model_for_source(source, Some(&session.session_id))
+            {
                 session.model = model;
             }
         }
         Some(session)
     }
 
+    /// Model metadata is often only known after reading the session file, so
+    /// model-based filters enrich candidates on demand (without the
+    /// most-recent-model fallback `get_exact` applies for resume).
+    fn with_enriched_model(&mut self, session: SessionInfo) -> SessionInfo {
+        if !session.model.trim().is_empty() {
+            return session;
+        }
+        self.enrich_session_for_access(session.source, &session.session_id);
+        self.sessions
+            .get(&session.source.internal_key(&session.session_id))
+            .cloned()
+            .unwrap_or(session)
+    }
+
     fn get(&mut self, session_id: &str) -> Option<SessionInfo> {
         self.load();
         let mut exact_matches = Vec::new();
@@ -1186,6 +2293,146 @@ impl SessionStore {
         None
     }
 
+    /// `get` for a session ID prefix, session file path, or row number (`#`)
+    /// from the last `cs list` table, with an error that says why the argument
+    /// didn't resolve. Row numbers the last list doesn't have fall back to ID
+    /// matching.
+    fn resolve_session_arg(&mut self, arg: &str) -> Result<SessionInfo> {
+        let arg = arg.trim();
+        if let Some(row) = list_row_arg(arg) {
+            if let Some((source, session_id)) = LastList::load().sessions.get(row - 1) {
+                return self.get_exact(*source, session_id).ok_or_else(|| {
+                    anyhow!("Session from row {row} of the last `cs list` is gone: {session_id}")
+                });
+            }
+        }
+        self.resolve_session_id(arg)
+    }
+
+    /// Like `resolve_session_arg` but without row numbers, for commands that
+    /// change or remove sessions: a row from some other terminal's `cs list`
+    /// must never pick the target.
+    fn resolve_session_id(&mut self, arg: &str) -> Result<SessionInfo> {
+        let arg = arg.trim();
+        if let Some(session) = self.get(arg) {
+            if arg.len() < 4 && session.session_id != arg {
+                eprintln!(
+                    "cs-rs: warning: '{arg}' is a very short ID prefix; matched {}",
+                    session.session_id
+                );
+            }
+            return Ok(session);
+        }
+
+        if arg.contains(['/', '\\']) || session_file_stem(arg).is_some() {
+            return Err(anyhow!(
+                "No such session file: {arg} (view accepts a session ID or the path to an existing .jsonl file)"
+            ));
+        }
+        if arg.len() < 4 {
+            return Err(anyhow!(
+                "Session ID prefix '{arg}' is too short; use at least 4 characters"
+            ));
+        }
+        if !Self::looks_like_session_id(arg)
+            && !arg.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+        {
+            return Err(anyhow!(
+                "'{arg}' doesn't look like a session ID (expected a UUID or a hex prefix of one)"
+            ));
+        }
+        let prefix_matches = self
+            .all()
+            .iter()
+            .filter(|s| s.session_id.starts_with(arg))
+            .count();
+        if prefix_matches > 1 {
+            return Err(anyhow!(
+                "Session ID prefix '{arg}' is ambiguous ({prefix_matches} matches); add more characters"
+            ));
+        }
+        Err(anyhow!("Session not found: {arg}"))
+    }
+
+    /// A Claude Code transcript in its project directory, or in
+    /// `archived_sessions` after `cs archive`.
+    fn claudecode_session_file(project: &str, session_id: &str) -> Option<PathBuf> {
+        let source = SessionSource::Claudecode;
+        let mut dirs = vec![source.archived_sessions_dir()];
+        if !project.is_empty() {
+            dirs.insert(0, source.projects_dir().join(Self::encode_path(project)));
+        }
+        dirs.iter().find_map(|dir| {
+            SESSION_FILE_SUFFIXES
+                .iter()
+                .map(|suffix| dir.join(format!("{session_id}{suffix}")))
+                .find(|candidate| candidate.exists())
+        })
+    }
+
+    /// Moves the session file into the source's `archived_sessions` directory.
+    /// The session leaves the default list (`--archived` brings it back) but
+    /// stays viewable by ID.
+    fn archive_session(&mut self, session: &SessionInfo) -> Result<PathBuf> {
+        let path = session
+            .file_path
+            .as_deref()
+            .map(PathBuf::from)
+            .with_context(|| format!("No session file found for {}", session.session_id))?;
+        let archive_dir = session.source.archived_sessions_dir();
+        if path.starts_with(&archive_dir) {
+            return Err(anyhow!("Already archived: {}", path.display()));
+        }
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("Not a file path: {}", path.display()))?;
+        let dest = archive_dir.join(file_name);
+        if dest.exists() {
+            return Err(anyhow!("Archive destination exists: {}", dest.display()));
+        }
+        fs::create_dir_all(&archive_dir)
+            .with_context(|| format!("failed to create {}", archive_dir.display()))?;
+        fs::rename(&path, &dest)
+            .with_context(|| format!("failed to move {} to {}", path.display(), dest.display()))?;
+
+        let key = session.source.internal_key(&session.session_id);
+        if let Some(entry) = self.sessions.get_mut(&key) {
+            entry.file_path = Some(dest.to_string_lossy().to_string());
+        }
+        if session.source == SessionSource::Codex {
+            self.update_codex_cache(&session.session_id, &dest, None);
+        }
+        self.save_cache_if_dirty();
+        Ok(dest)
+    }
+
+    /// Deletes the session file and drops the session from the cache so it no
+    /// longer shows up, even though its history lines remain.
+    fn delete_session(&mut self, session: &SessionInfo) -> Result<()> {
+        if let Some(path) = session.file_path.as_deref() {
+            match fs::remove_file(path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err).with_context(|| format!("failed to delete {path}")),
+            }
+        }
+
+        let key = session.source.internal_key(&session.session_id);
+        self.sessions.remove(&key);
+        self.search_text_cache.remove(&key);
+        self.cache.tags.remove(&key);
+        self.cache.message_counts.remove(&key);
+        if session.source == SessionSource::Codex {
+            self.cache.codex_sessions.remove(&session.session_id);
+        }
+        if !self.cache.deleted.contains(&key) {
+            self.cache.deleted.push(key);
+        }
+        self.cache_dirty = true;
+        self.save_cache_if_dirty();
+        Ok(())
+    }
+
     fn find_session_file(
         &self,
         source: SessionSource,
@@ -1193,13 +2440,12 @@ impl SessionStore {
         project: &str,
     ) -> Option<PathBuf> {
         if !project.is_empty() {
-            let encoded = Self::encode_path(project);
-            let candidate = source
-                .projects_dir()
-                .join(encoded)
-                .join(format!("{session_id}.jsonl"));
-            if candidate.exists() {
-                return Some(candidate);
+            let project_dir = source.projects_dir().join(Self::encode_path(project));
+            for suffix in SESSION_FILE_SUFFIXES {
+                let candidate = project_dir.join(format!("{session_id}{suffix}"));
+                if candidate.exists() {
+                    return Some(candidate);
+                }
             }
         }
 
@@ -1222,13 +2468,21 @@ impl SessionStore {
             return None;
         }
 
+        if source == SessionSource::Claudecode {
+            if let Some(found) = Self::claudecode_session_file("", session_id) {
+                return Some(found);
+            }
+        }
+
         let readdir = fs::read_dir(projects_dir).ok()?;
         for entry in readdir.filter_map(Result::ok) {
             let p = entry.path();
             if p.is_dir() {
-                let cand = p.join(format!("{session_id}.jsonl"));
-                if cand.exists() {
-                    return Some(cand);
+                for suffix in SESSION_FILE_SUFFIXES {
+                    let cand = p.join(format!("{session_id}{suffix}"));
+                    if cand.exists() {
+                        return Some(cand);
+                    }
                 }
             }
         }
@@ -1248,13 +2502,19 @@ impl SessionStore {
         for entry in entries.filter_map(Result::ok) {
             let p = entry.path();
             if p.is_file() {
-                let is_jsonl = p.extension().and_then(|ext| ext.to_str()) == Some("jsonl");
-                if !is_jsonl {
+                if !is_session_file(&p) {
                     continue;
                 }
 
                 let name = p.file_name().and_then(|name| name.to_str()).unwrap_or("");
-                if name.contains(session_id) {
+                // Non-UUID (legacy/custom) IDs can be short enough to appear
+                // inside unrelated names, so they must match the whole stem.
+                let found = if Self::looks_like_session_id(session_id) {
+                    name.contains(session_id)
+                } else {
+                    session_file_stem(name) == Some(session_id)
+                };
+                if found {
                     return Some(p);
                 }
                 continue;
@@ -1310,18 +2570,23 @@ impl SessionStore {
         true
     }
 
+    /// The trailing UUID of a session file name (`rollout-…-<uuid>.jsonl`).
+    /// Directory scans rely on this to skip files that aren't sessions, such
+    /// as Claude Code's `agent-*.jsonl` sidechain transcripts.
     fn session_id_from_file_name(path: &Path) -> Option<String> {
         let name = path.file_name()?.to_str()?;
-        let stem = name.strip_suffix(".jsonl")?;
-        if stem.len() < 36 {
-            return None;
-        }
-        let candidate = &stem[stem.len() - 36..];
-        if Self::looks_like_session_id(candidate) {
-            Some(candidate.to_string())
-        } else {
-            None
-        }
+        let stem = session_file_stem(name)?;
+        stem.get(stem.len().saturating_sub(36)..)
+            .filter(|candidate| Self::looks_like_session_id(candidate))
+            .map(str::to_string)
+    }
+
+    /// Like `session_id_from_file_name`, but falls back to the whole stem for
+    /// legacy/custom IDs that aren't UUIDs. Only for a file the user named.
+    fn session_id_or_stem_from_file_name(path: &Path) -> Option<String> {
+        let name = path.file_name()?.to_str()?;
+        let stem = session_file_stem(name).filter(|stem| !stem.is_empty())?;
+        Self::session_id_from_file_name(path).or_else(|| Some(stem.to_string()))
     }
 
     fn recent_codex_day_dirs(limit: usize) -> Vec<PathBuf> {
@@ -1352,7 +2617,7 @@ impl SessionStore {
                 if !path.is_file() {
                     continue;
                 }
-                if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                if !is_session_file(&path) {
                     continue;
                 }
                 if let Some(session_id) = Self::session_id_from_file_name(&path) {
@@ -1368,7 +2633,7 @@ impl SessionStore {
         for entry in entries.filter_map(Result::ok) {
             let path = entry.path();
             if path.is_file() {
-                if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                if !is_session_file(&path) {
                     continue;
                 }
                 if let Some(session_id) = Self::session_id_from_file_name(&path) {
@@ -1383,7 +2648,7 @@ impl SessionStore {
                     if !nested_path.is_file() {
                         continue;
                     }
-                    if nested_path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                    if !is_session_file(&nested_path) {
                         continue;
                     }
                     if let Some(session_id) = Self::session_id_from_file_name(&nested_path) {
@@ -1396,13 +2661,109 @@ impl SessionStore {
         index
     }
 
+    /// Transcripts on disk whose session IDs never made it into (or fell out
+    /// of) the history files, sorted by path.
+    fn orphaned_session_files(&mut self) -> Vec<(SessionSource, PathBuf)> {
+        // Compare against every ID the history files name, not the loaded
+        // list: collapsed, deleted, ignored, and empty sessions are still
+        // known to history, so their transcripts aren't orphans.
+        let mut in_history: Vec<String> = Vec::new();
+        for source in SessionSource::all() {
+            in_history.extend(self.load_sessions_for_source(*source).into_keys());
+        }
+        self.save_cache_if_dirty();
+        in_history.sort_unstable();
+        let mut files: Vec<(SessionSource, String, PathBuf)> = Vec::new();
+
+        let claude = SessionSource::Claudecode;
+        for project_dir in Self::sorted_child_dirs_desc(&claude.projects_dir()) {
+            let Ok(entries) = fs::read_dir(&project_dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                if !path.is_file() || !is_session_file(&path) {
+                    continue;
+                }
+                if let Some(session_id) = Self::session_id_from_file_name(&path) {
+                    files.push((claude, session_id, path));
+                }
+            }
+        }
+        for (session_id, path) in Self::build_recent_codex_file_index(usize::MAX) {
+            files.push((SessionSource::Codex, session_id, path));
+        }
+
+        let mut orphans: Vec<(SessionSource, PathBuf)> = files
+            .into_iter()
+            .filter(|(source, id, _)| in_history.binary_search(&source.internal_key(id)).is_err())
+            .map(|(source, _, path)| (source, path))
+            .collect();
+        orphans.sort_by(|a, b| a.1.cmp(&b.1));
+        orphans
+    }
+
+    fn doctor_report(&mut self) -> DoctorReport {
+        self.load();
+        let cache_path = Self::cache_file_path();
+        let cache_status = match fs::read_to_string(&cache_path) {
+            Ok(raw) => match serde_json::from_str::<SessionCache>(&raw) {
+                Ok(_) => "readable".to_string(),
+                Err(err) => format!("invalid JSON ({err})"),
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => "not created yet".to_string(),
+            Err(err) => format!("unreadable ({err})"),
+        };
+        let corrupt_backup = Some(cache_path.with_extension("json.corrupt")).filter(|p| p.exists());
+        let sources = SessionSource::all()
+            .iter()
+            .map(|source| {
+                let rotated = source
+                    .rotated_history_files()
+                    .into_iter()
+                    .map(|path| ("rotated", path));
+                let paths = [("history", source.history_file())]
+                    .into_iter()
+                    .chain(rotated)
+                    .chain([
+                        ("projects", source.projects_dir()),
+                        ("sessions", source.sessions_dir()),
+                        ("archived", source.archived_sessions_dir()),
+                    ])
+                    .map(|(name, path)| {
+                        let exists = path.exists();
+                        (name, path, exists)
+                    })
+                    .collect();
+                SourceDiagnostics {
+                    source: *source,
+                    paths,
+                    counts: self.load_counts.get(source).copied().unwrap_or_default(),
+                    loaded: self
+                        .sessions
+                        .values()
+                        .filter(|s| s.source == *source)
+                        .count(),
+                }
+            })
+            .collect();
+        DoctorReport {
+            home: home_dir(),
+            data_home: data_home(),
+            cache_path,
+            cache_status,
+            corrupt_backup,
+            ignore_path: IgnoreRules::file_path(),
+            sources,
+        }
+    }
+
     fn codex_file_info_from_session_file(
         &self,
         path: &Path,
         expected_session_id: &str,
     ) -> Option<CodexSessionFileInfo> {
-        let file = File::open(path).ok()?;
-        let reader = BufReader::new(file);
+        let reader = open_session_file(path).ok()?;
         let mut out = CodexSessionFileInfo::default();
         let mut saw_session_meta = false;
         let mut current_session_matches = true;
@@ -1433,8 +2794,13 @@ impl SessionStore {
                     .get("id")
                     .and_then(Value::as_str)
                     .unwrap_or_default();
-                current_session_matches = id.is_empty() || id == expected_session_id;
-                if !id.is_empty() && id != expected_session_id {
+                // Rolled-over files can hold several sessions, each starting
+                // at its own `session_meta`; an ID-less meta continues the
+                // current segment.
+                if !id.is_empty() {
+                    current_session_matches = id == expected_session_id;
+                }
+                if !current_session_matches {
                     continue;
                 }
 
@@ -1457,8 +2823,17 @@ impl SessionStore {
                     .and_then(Value::as_str)
                     .and_then(codex_iso_to_ms)
                     .or_else(|| codex_iso_to_ms(&parsed.timestamp));
+                // A resumed session can repeat its meta later in the file;
+                // the session started at the earliest one.
                 if let Some(ts) = ts {
-                    out.timestamp_ms = Some(ts);
+                    out.timestamp_ms = Some(out.timestamp_ms.map_or(ts, |seen| seen.min(ts)));
+                }
+                if let Some(title) = ["title", "instructions"]
+                    .iter()
+                    .filter_map(|field| payload.get(*field).and_then(Value::as_str))
+                    .find_map(codex_title_candidate)
+                {
+                    out.title = Some(title);
                 }
                 continue;
             }
@@ -1516,6 +2891,17 @@ impl SessionStore {
                         out.model = Some(model);
                     }
                 }
+                if out.title.is_none()
+                    && payload.get("role").and_then(Value::as_str) == Some("user")
+                {
+                    out.title = payload
+                        .get("content")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(block_text)
+                        .find_map(|text| codex_title_candidate(&text));
+                }
             }
         }
 
@@ -1523,6 +2909,7 @@ impl SessionStore {
             && out.timestamp_ms.is_none()
             && out.model.is_none()
             && out.reasoning_effort.is_none()
+            && out.title.is_none()
         {
             None
         } else {
@@ -1531,8 +2918,7 @@ impl SessionStore {
     }
 
     fn claudecode_model_from_session_file(path: &Path) -> Option<String> {
-        let file = File::open(path).ok()?;
-        let reader = BufReader::new(file);
+        let reader = open_session_file(path).ok()?;
         let mut latest_model = None;
 
         for line in reader.lines().map_while(Result::ok) {
@@ -1561,24 +2947,124 @@ impl SessionStore {
         latest_model
     }
 
-    fn read_messages(&self, session: &SessionInfo, skip_internal: bool) -> Vec<Message> {
-        let path = match session.file_path.as_deref() {
-            Some(p) => p,
-            None => return Vec::new(),
-        };
-        let contents = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => return Vec::new(),
-        };
+    /// The transcript format, judged from the file's contents first and its
+    /// location second (a Claude transcript copied under `.codex` is still
+    /// Claude). Defaults to Claude Code.
+    fn source_for_file(path: &Path) -> SessionSource {
+        if let Some(source) = sniff_transcript_source(path) {
+            return source;
+        }
+        let codex_home = SessionSource::Codex.home_base();
+        if path.starts_with(&codex_home)
+            || path
+                .components()
+                .any(|component| component.as_os_str() == ".codex")
+        {
+            return SessionSource::Codex;
+        }
+        SessionSource::Claudecode
+    }
 
-        let mut out = Vec::new();
-        for line in contents.lines() {
-            let line = line.trim();
+    /// Which parser reads `path` for `session`. Files under the session's own
+    /// source directory use its parser; anything else (backups, exports,
+    /// files passed by path) is sniffed so a mislabeled source still parses.
+    fn parser_source(session: &SessionInfo, path: &Path) -> SessionSource {
+        if path.starts_with(session.source.home_base()) {
+            return session.source;
+        }
+        sniff_transcript_source(path).unwrap_or(session.source)
+    }
+
+    /// Builds a standalone session for a transcript that may not be indexed in
+    /// any history file (e.g. a backup copy), for `cs view <path>`.
+    fn session_from_file(&self, path: &Path) -> SessionInfo {
+        let source = Self::source_for_file(path);
+        let session_id = Self::session_id_or_stem_from_file_name(path).unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string()
+        });
+        let mut session = SessionInfo {
+            source,
+            session_id,
+            display: String::new(),
+            project: String::new(),
+            timestamp: fs::metadata(path)
+                .ok()
+                .and_then(|metadata| Self::metadata_modified_ms(&metadata))
+                .unwrap_or(0),
+            model: String::new(),
+            reasoning_effort: String::new(),
+            file_path: Some(path.to_string_lossy().to_string()),
+        };
+
+        match source {
+            SessionSource::Codex => {
+                if let Some(info) =
+                    self.codex_file_info_from_session_file(path, &session.session_id)
+                {
+                    session.project = info.cwd.unwrap_or_default();
+                    session.timestamp = info.timestamp_ms.unwrap_or(session.timestamp);
+                    session.model = info.model.unwrap_or_default();
+                    session.reasoning_effort = info.reasoning_effort.unwrap_or_default();
+                }
+            }
+            SessionSource::Claudecode => {
+                session.model = Self::claudecode_model_from_session_file(path).unwrap_or_default();
+                if let Ok(reader) = open_session_file(path) {
+                    session.project = reader
+                        .lines()
+                        .map_while(Result::ok)
+                        .filter_map(|line| serde_json::from_str::<Value>(line.trim()).ok())
+                        .find_map(|value| {
+                            value.get("cwd").and_then(Value::as_str).map(str::to_string)
+                        })
+                        .unwrap_or_default();
+                }
+            }
+        }
+
+        session.display = self
+            .read_messages(&session, true)
+            .iter()
+            .find(|msg| msg.msg_type == "user")
+            .map(Message::text)
+            .filter(|text| !text.is_empty())
+            .unwrap_or_else(|| path.display().to_string());
+        session
+    }
+
+    fn read_messages(&self, session: &SessionInfo, skip_internal: bool) -> Vec<Message> {
+        self.read_last_messages(session, skip_internal, None)
+    }
+
+    /// Streams the session file line by line. With `tail`, only the last N
+    /// messages are kept in memory while reading.
+    fn read_last_messages(
+        &self,
+        session: &SessionInfo,
+        skip_internal: bool,
+        tail: Option<usize>,
+    ) -> Vec<Message> {
+        let path = match session.file_path.as_deref() {
+            Some(p) => p,
+            None => return Vec::new(),
+        };
+        let reader = match open_session_file(Path::new(path)) {
+            Ok(r) => r,
+            Err(_) => return Vec::new(),
+        };
+        let source = Self::parser_source(session, Path::new(path));
+
+        let mut out = VecDeque::new();
+        for line in reader.lines().map_while(Result::ok) {
+            let line = line.trim();
             if line.is_empty() {
                 continue;
             }
 
-            let msg = match session.source {
+            let msg = match source {
                 SessionSource::Claudecode => match serde_json::from_str::<RawMessage>(line) {
                     Ok(raw) => Some(Message::from(raw)),
                     Err(_) => None,
@@ -1592,9 +3078,56 @@ impl SessionStore {
             if skip_internal && INTERNAL_TYPES.contains(&msg.msg_type.as_str()) {
                 continue;
             }
-            out.push(msg);
+            out.push_back(msg);
+            if tail.is_some_and(|n| out.len() > n) {
+                out.pop_front();
+            }
         }
-        out
+        out.into()
+    }
+
+    /// Number of user/assistant messages in the session file, cached until the
+    /// file changes.
+    fn message_count(&mut self, session: &SessionInfo) -> usize {
+        self.message_stats(session).count
+    }
+
+    /// How long the session ran, from its first to its last message.
+    fn session_duration_ms(&mut self, session: &SessionInfo) -> Option<i64> {
+        session.file_path.as_ref()?;
+        self.message_stats(session).duration_ms
+    }
+
+    /// Message count and duration, cached until the session file changes.
+    fn message_stats(&mut self, session: &SessionInfo) -> CachedMessageCount {
+        let key = session.source.internal_key(&session.session_id);
+        let (file_size, file_modified_ms) =
+            Self::search_text_signature(session.file_path.as_deref());
+        if let Some(cached) = self.cache.message_counts.get(&key) {
+            if cached.file_size == file_size
+                && cached.file_modified_ms == file_modified_ms
+                && cached.version == MESSAGE_COUNT_VERSION
+            {
+                return cached.clone();
+            }
+        }
+
+        let msgs = self.read_messages(session, true);
+        let turns: Vec<&Message> = msgs
+            .iter()
+            .filter(|msg| msg.is_conversation_turn())
+            .collect();
+        let stats = CachedMessageCount {
+            version: MESSAGE_COUNT_VERSION,
+            file_size,
+            file_modified_ms,
+            count: turns.len(),
+            duration_ms: conversation_duration_ms(&msgs),
+            user_turns: Some(turns.iter().filter(|msg| msg.msg_type == "user").count()),
+        };
+        self.cache.message_counts.insert(key, stats.clone());
+        self.cache_dirty = true;
+        stats
     }
 
     fn session_contains_full_text(&mut self, session: &SessionInfo, query: &str) -> bool {
@@ -1642,47 +3175,74 @@ impl SessionStore {
     fn search(
         &mut self,
         query: &str,
-        project: Option<&str>,
-        max_results: usize,
+        options: &SearchOptions,
     ) -> Result<Vec<(SessionInfo, Message, String)>> {
         self.load();
+        let match_mode = options.match_mode;
 
-        let pattern =
-            Regex::new(&format!("(?i){query}")).map_err(|err| anyhow!("invalid regex: {err}"))?;
-
+        let terms: Vec<&str> = match match_mode {
+            Some(_) => query.split_whitespace().collect(),
+            None => vec![query],
+        };
+        let patterns = terms
+            .iter()
+            .map(|term| Regex::new(&format!("(?i){term}")))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow!("invalid regex: {err}"))?;
+        let require_all = match_mode == Some(MatchMode::All);
+
+        let candidates = match &options.session {
+            Some(session) => vec![session.clone()],
+            None => self.all(),
+        };
+        let all_hits = options.session.is_some();
+        let fuzzy_project = options.project_fuzzy || config().fuzzy_project;
+        let max = unlimited_if_zero(options.max_results);
+        let workers = thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(SEARCH_MAX_WORKERS);
+
+        // Candidates are scanned in batches so a small `--max` still stops
+        // early; within a batch sessions are read in parallel, and hits are
+        // collected in candidate (newest-first) order so output is stable.
         let mut results: Vec<(SessionInfo, Message, String)> = Vec::new();
-        for session in self.all() {
-            self.enrich_session_for_access(session.source, &session.session_id);
-            let session = self
-                .sessions
-                .get(&session.source.internal_key(&session.session_id))
-                .cloned()
-                .unwrap_or(session);
-
-            if let Some(p) = project {
-                if !session.project.to_lowercase().contains(&p.to_lowercase()) {
-                    continue;
+        'batches: for batch in candidates.chunks(workers * SEARCH_BATCH_PER_WORKER) {
+            let mut sessions = Vec::with_capacity(batch.len());
+            for session in batch {
+                self.enrich_session_for_access(session.source, &session.session_id);
+                let session = self
+                    .sessions
+                    .get(&session.source.internal_key(&session.session_id))
+                    .cloned()
+                    .unwrap_or_else(|| session.clone());
+
+                if let Some(p) = &options.project {
+                    if !p.matches(&session.project, fuzzy_project) {
+                        continue;
+                    }
                 }
+                if let Some(since_ms) = options.since_ms {
+                    if session.timestamp < since_ms {
+                        continue;
+                    }
+                }
+                if let Some(m) = options.model.as_deref() {
+                    if !session.model.to_lowercase().contains(&m.to_lowercase()) {
+                        continue;
+                    }
+                }
+                sessions.push(session);
             }
 
-            for msg in self.read_messages(&session, true) {
-                let text = msg.text();
-                if text.is_empty() {
-                    continue;
-                }
-                let found = text
-                    .lines()
-                    .map(str::trim)
-                    .filter(|line| !line.is_empty())
-                    .find(|line| pattern.is_match(line));
-
-                if let Some(line) = found {
-                    results.push((session.clone(), msg.clone(), line.to_string()));
-                    if results.len() >= max_results {
-                        self.save_cache_if_dirty();
-                        return Ok(results);
+            let hits = parallel_map(&sessions, workers, |session| {
+                self.search_session(session, &patterns, require_all, all_hits)
+            });
+            for (session, session_hits) in sessions.iter().zip(hits) {
+                for (msg, line) in session_hits {
+                    results.push((session.clone(), msg, line));
+                    if results.len() >= max {
+                        break 'batches;
                     }
-                    break;
                 }
             }
         }
@@ -1691,7 +3251,75 @@ impl SessionStore {
         Ok(results)
     }
 
-    fn build_stats_report(&mut self) -> StatsReport {
+    /// Matching messages in one session, each with the first matching line.
+    /// Across sessions only the first hit matters; within a single session
+    /// (`all_hits`) every matching message is returned.
+    fn search_session(
+        &self,
+        session: &SessionInfo,
+        patterns: &[Regex],
+        require_all: bool,
+        all_hits: bool,
+    ) -> Vec<(Message, String)> {
+        let msgs = self.read_messages(session, true);
+        if require_all {
+            let full_text = msgs
+                .iter()
+                .map(Message::text)
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !patterns.iter().all(|p| p.is_match(&full_text)) {
+                return Vec::new();
+            }
+        }
+
+        let mut hits = Vec::new();
+        for msg in msgs {
+            let text = msg.text();
+            if text.is_empty() {
+                continue;
+            }
+            let found = text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .find(|line| patterns.iter().any(|p| p.is_match(line)));
+
+            if let Some(line) = found {
+                let line = line.to_string();
+                hits.push((msg, line));
+                if !all_hits {
+                    break;
+                }
+            }
+        }
+        hits
+    }
+
+    fn estimate_session_cost_usd(&self, session: &SessionInfo) -> f64 {
+        let mut cost = 0.0;
+        for msg in self.read_messages(session, true) {
+            let model = if msg.model().is_empty() {
+                session.model.as_str()
+            } else {
+                msg.model()
+            };
+            let Some(rate) = config().cost_rate_for_model(model) else {
+                continue;
+            };
+            let tokens = msg.text().chars().count() as f64 / CHARS_PER_TOKEN;
+            cost += tokens / 1000.0 * rate;
+        }
+        cost
+    }
+
+    fn build_stats_report(
+        &mut self,
+        estimate_cost: bool,
+        count_turns: bool,
+        days: usize,
+        group_models: bool,
+    ) -> StatsReport {
         self.load();
 
         // Stats are the one place we can pay a little extra cost to enrich missing
@@ -1733,7 +3361,11 @@ impl SessionStore {
             })
             .sum::<u64>();
 
-        let last_computed_date = Local::now().format("%Y-%m-%d").to_string();
+        let today = display_zone().now().date_naive();
+        let last_computed_date = today.format("%Y-%m-%d").to_string();
+        let window_start = days_before(today, days.saturating_sub(1))
+            .format("%Y-%m-%d")
+            .to_string();
 
         let mut sources = Vec::new();
         for source in SessionSource::all() {
@@ -1741,22 +3373,49 @@ impl SessionStore {
             let mut first_session_ts: Option<i64> = None;
             let mut model_counts: HashMap<String, u64> = HashMap::new();
             let mut daily_sessions: BTreeMap<String, u64> = BTreeMap::new();
+            let mut estimated_cost_usd = estimate_cost.then_some(0.0);
+
+            // Turn counts read each session file once, then come from the cache.
+            let turns = count_turns.then(|| {
+                let with_files: Vec<SessionInfo> = self
+                    .sessions
+                    .values()
+                    .filter(|s| s.source == *source && s.file_path.is_some())
+                    .cloned()
+                    .collect();
+                let (mut user_turns, mut assistant_turns) = (0u64, 0u64);
+                for session in &with_files {
+                    let stats = self.message_stats(session);
+                    let user = stats.user_turns.unwrap_or(0);
+                    user_turns += user as u64;
+                    assistant_turns += stats.count.saturating_sub(user) as u64;
+                }
+                (user_turns, assistant_turns)
+            });
 
             for session in self.sessions.values().filter(|s| s.source == *source) {
                 sessions += 1;
+                if let Some(total) = estimated_cost_usd.as_mut() {
+                    *total += self.estimate_session_cost_usd(session);
+                }
                 if session.timestamp > 0 {
                     first_session_ts = Some(
                         first_session_ts
                             .map(|existing| existing.min(session.timestamp))
                             .unwrap_or(session.timestamp),
                     );
-                    if let Some(ts) = Local.timestamp_millis_opt(session.timestamp).single() {
+                    if let Some(ts) = display_time(session.timestamp) {
                         let day = ts.format("%Y-%m-%d").to_string();
                         *daily_sessions.entry(day).or_insert(0) += 1;
                     }
                 }
                 if !session.model.trim().is_empty() {
-                    *model_counts.entry(session.model.clone()).or_insert(0) += 1;
+                    let model = if group_models {
+                        model_family(&session.model)
+                    } else {
+                        session.model.clone()
+                    };
+                    *model_counts.entry(model).or_insert(0) += 1;
                 }
             }
 
@@ -1768,17 +3427,16 @@ impl SessionStore {
                 .unwrap_or(0);
 
             let mut top_models: Vec<(String, u64)> = model_counts.into_iter().collect();
-            top_models.sort_by(|a, b| b.1.cmp(&a.1));
+            top_models.sort_by_key(|(_, count)| Reverse(*count));
             top_models.truncate(8);
 
-            let mut daily_sessions: Vec<(String, u64)> = daily_sessions.into_iter().collect();
-            if daily_sessions.len() > 14 {
-                let keep_from = daily_sessions.len() - 14;
-                daily_sessions = daily_sessions.into_iter().skip(keep_from).collect();
-            }
+            let daily_sessions: Vec<(String, u64)> = daily_sessions
+                .into_iter()
+                .filter(|(day, _)| *day >= window_start)
+                .collect();
 
             let first_session_date = first_session_ts
-                .and_then(|ts| Local.timestamp_millis_opt(ts).single())
+                .and_then(display_time)
                 .map(|dt| dt.format("%Y-%m-%d").to_string())
                 .unwrap_or_else(|| "—".to_string());
 
@@ -1789,6 +3447,8 @@ impl SessionStore {
                 first_session_date,
                 top_models,
                 daily_sessions,
+                estimated_cost_usd,
+                turns,
             });
         }
 
@@ -1796,6 +3456,7 @@ impl SessionStore {
             total_sessions,
             total_history_entries,
             last_computed_date,
+            days,
             sources,
         };
         self.save_cache_if_dirty();
@@ -1803,6 +3464,43 @@ impl SessionStore {
     }
 }
 
+/// Strips build suffixes so model variants group together:
+/// `claude-opus-4-6-20260101` and `claude-opus-4-6[1m]` -> `claude-opus-4-6`.
+fn model_family(model: &str) -> String {
+    let mut model = model.trim();
+    loop {
+        let before = model;
+        if let Some(idx) = model.rfind('[').filter(|_| model.ends_with(']')) {
+            model = &model[..idx];
+        }
+        if let Some(idx) = model.rfind('@') {
+            model = &model[..idx];
+        }
+        if let Some((head, tail)) = model.rsplit_once('-') {
+            let is_date = tail.len() == 8 && tail.bytes().all(|b| b.is_ascii_digit());
+            let is_version = tail.len() > 1
+                && tail.starts_with('v')
+                && tail[1..].bytes().all(|b| b.is_ascii_digit() || b == b':');
+            if (is_date || is_version || tail == "latest") && !head.is_empty() {
+                model = head;
+            }
+        }
+        // ISO dates (`-2025-04-14`) split into three dash-separated parts.
+        let split = model.len().saturating_sub(11);
+        if let (Some(head), Some(date)) = (model.get(..split), model.get(split..)) {
+            let is_iso = date
+                .strip_prefix('-')
+                .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+            if is_iso && !head.is_empty() {
+                model = head;
+            }
+        }
+        if model == before {
+            return model.to_string();
+        }
+    }
+}
+
 fn relative_time(ts_ms: i64) -> String {
     list_time(ts_ms)
 }
@@ -1812,28 +3510,150 @@ fn is_worktree(project: &str) -> bool {
 }
 
 fn short_project(project: &str) -> String {
+    // Relative paths (rare, from old or hand-edited history) get a `./` so
+    // they aren't mistaken for a directory under the current one's name.
+    if !project.is_empty() && absolute_project_path(project).is_none() {
+        return if project.starts_with('.') {
+            project.to_string()
+        } else {
+            format!("./{project}")
+        };
+    }
     let home = home_dir();
     let home_s = home.to_string_lossy();
-    if let Some(rest) = project.strip_prefix(home_s.as_ref()) {
+    let project = if let Some(rest) = project.strip_prefix(home_s.as_ref()) {
         format!("~{rest}")
     } else {
         project.to_string()
+    };
+    if let Some(alias) = config().project_alias(&project) {
+        return alias;
+    }
+    collapse_path(&project, config().project_tail_components.unwrap_or(2))
+}
+
+/// Keeps the root, the first directory's initial, and the last `keep`
+/// components: `~/work/acme/services/api/crate` -> `~/w/…/api/crate`.
+fn collapse_path(path: &str, keep: usize) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    // parts[0] is "~" or "" (absolute root); parts[1] is the first directory.
+    if keep == 0 || parts.len() <= keep + 3 {
+        return path.to_string();
     }
+    let first = parts[1]
+        .chars()
+        .next()
+        .map(String::from)
+        .unwrap_or_default();
+    let tail = parts[parts.len() - keep..].join("/");
+    format!("{}/{first}/…/{tail}", parts[0])
 }
 
+/// Collapses whitespace and cuts to `width` grapheme clusters, so emoji with
+/// modifiers and combining accents are never split.
 fn truncate(text: &str, width: usize) -> String {
     let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
-    let count = text.chars().count();
+    let count = text.graphemes(true).count();
     if count <= width {
         text
     } else {
-        text.chars()
+        text.graphemes(true)
             .take(width.saturating_sub(1))
             .collect::<String>()
             + "…"
     }
 }
 
+/// Like `truncate`, but measured in terminal columns (CJK and emoji count as
+/// two) and right-padded with spaces to exactly `width` columns.
+fn fit_width(text: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = String::new();
+    let mut used = 0;
+    if text.width() <= width {
+        out = text;
+        used = out.width();
+    } else {
+        for g in text.graphemes(true) {
+            let w = g.width();
+            if used + w + 1 > width {
+                break;
+            }
+            out.push_str(g);
+            used += w;
+        }
+        out.push('…');
+        used += 1;
+    }
+    out.push_str(&" ".repeat(width.saturating_sub(used)));
+    out
+}
+
+/// Hard-wraps every line to `width` terminal columns, breaking at spaces and
+/// splitting words too long for a line. Continuation lines keep the line's
+/// indentation unless it would take half the width.
+fn wrap_text(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for line in text.split('\n') {
+        if line.width() <= width {
+            out.push(line.to_string());
+            continue;
+        }
+        let body = line.trim_start();
+        let indent = &line[..line.len() - body.len()];
+        let indent = if indent.width() * 2 < width {
+            indent
+        } else {
+            ""
+        };
+        let avail = width - indent.width();
+
+        let mut current = String::new();
+        let mut used = 0;
+        for word in body.split(' ') {
+            let word_width = word.width();
+            if used > 0 && used + 1 + word_width <= avail {
+                current.push(' ');
+                current.push_str(word);
+                used += 1 + word_width;
+                continue;
+            }
+            if used > 0 {
+                out.push(format!("{indent}{current}"));
+                current.clear();
+                used = 0;
+            }
+            for g in word.graphemes(true) {
+                let g_width = g.width();
+                if used > 0 && used + g_width > avail {
+                    out.push(format!("{indent}{current}"));
+                    current.clear();
+                    used = 0;
+                }
+                current.push_str(g);
+                used += g_width;
+            }
+        }
+        out.push(format!("{indent}{current}"));
+    }
+    out.join("\n")
+}
+
+/// Columns for `view --wrap-width` without a value: the terminal's width, or
+/// 80 when stdout isn't a terminal.
+fn default_wrap_width() -> usize {
+    if io::stdout().is_terminal() {
+        if let Ok((cols, _)) = crossterm::terminal::size() {
+            return cols as usize;
+        }
+    }
+    80
+}
+
 fn session_id_hex_tail(session_id: &str, count: usize) -> String {
     let hex_chars: Vec<char> = session_id
         .chars()
@@ -1853,12 +3673,220 @@ fn session_id_hex_tail(session_id: &str, count: usize) -> String {
         .collect()
 }
 
+/// `truncate`, unless the full text was asked for (`view --no-truncate`).
+fn clip(text: &str, width: usize, full: bool) -> String {
+    if full {
+        text.trim().to_string()
+    } else {
+        truncate(text, width)
+    }
+}
+
+fn tool_use_summary(name: &str, input: &Value, full: bool) -> String {
+    match name {
+        "Bash" => {
+            let cmd = input.get("command").and_then(Value::as_str).unwrap_or("");
+            let desc = input
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let detail = if desc.is_empty() { cmd } else { desc };
+            format!("$ {}", clip(detail, 80, full))
+        }
+        "Read" | "Edit" | "Write" | "Glob" | "Grep" => {
+            let target = input
+                .get("file_path")
+                .or_else(|| input.get("pattern"))
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            format!("{name} {target}")
+        }
+        "Task" => {
+            let desc = input
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            format!("Task {desc}")
+        }
+        "WebSearch" => {
+            let query = input.get("query").and_then(Value::as_str).unwrap_or("");
+            format!("Search: {query}")
+        }
+        "shell" | "exec_command" | "local_shell" => {
+            let cmd = match input.get("command").or_else(|| input.get("cmd")) {
+                Some(Value::Array(args)) => args
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                Some(Value::String(cmd)) => cmd.clone(),
+                _ => String::new(),
+            };
+            format!("$ {}", clip(&cmd, 80, full))
+        }
+        _ => format!("{name}(...)"),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct RenderOptions {
+    thinking: bool,
+    tail: Option<usize>,
+    /// Keep only the first N messages (the opening of the session).
+    head: Option<usize>,
+    show_internal: bool,
+    /// Turns render newest-first by default; this flips them to chronological order.
+    reverse: bool,
+    /// Drop `[tool]` lines, leaving only natural-language turns.
+    no_tools: bool,
+    /// Show only `[tool]` lines as a compact action log.
+    tools_only: bool,
+    /// Stamp each turn with the time it was recorded.
+    timestamps: bool,
+    /// Remove ANSI escape sequences (colored build logs in tool output).
+    strip_ansi: bool,
+    /// Show tool commands, thinking, and API errors in full.
+    no_truncate: bool,
+    /// Fold runs of consecutive tool calls into one summary line.
+    collapse_tools: bool,
+    /// Add a count of every message type, internal ones included, to the header.
+    stats: bool,
+}
+
+/// Distinct tool names listed in a collapsed run before eliding the rest.
+const COLLAPSED_TOOL_NAMES: usize = 4;
+
+/// `[12 tool calls: Read, Edit, Bash]` for a collapsed run of tool calls.
+fn collapsed_tools_summary(names: &[String]) -> String {
+    let mut distinct: Vec<&str> = Vec::new();
+    for name in names {
+        if !distinct.contains(&name.as_str()) {
+            distinct.push(name);
+        }
+    }
+    let more = if distinct.len() > COLLAPSED_TOOL_NAMES {
+        "…"
+    } else {
+        ""
+    };
+    distinct.truncate(COLLAPSED_TOOL_NAMES);
+    format!(
+        "[{} tool calls: {}{more}]",
+        names.len(),
+        distinct.join(", ")
+    )
+}
+
+/// One block of an assistant turn: the tool name for tool calls, the role
+/// it is styled as, and its rendered text.
+type TurnPart = (Option<String>, DetailRole, String);
+
+/// Folds each run of two or more adjacent tool parts (`Some(name)`) into one
+/// summary part.
+fn collapse_tool_parts(parts: Vec<TurnPart>) -> Vec<TurnPart> {
+    let mut out = Vec::new();
+    let mut run: Vec<(String, String)> = Vec::new();
+    let flush = |run: &mut Vec<(String, String)>, out: &mut Vec<TurnPart>| {
+        if run.len() > 1 {
+            let names: Vec<String> = run.iter().map(|(name, _)| name.clone()).collect();
+            out.push((None, DetailRole::Tool, collapsed_tools_summary(&names)));
+        } else {
+            out.extend(
+                run.iter()
+                    .map(|(name, text)| (Some(name.clone()), DetailRole::Tool, text.clone())),
+            );
+        }
+        run.clear();
+    };
+    for (name, role, text) in parts {
+        match name {
+            Some(name) => run.push((name, text)),
+            None => {
+                flush(&mut run, &mut out);
+                out.push((None, role, text));
+            }
+        }
+    }
+    flush(&mut run, &mut out);
+    out
+}
+
+/// What a line of a rendered conversation belongs to, for detail-view colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum DetailRole {
+    /// Header, separators, and internal markers.
+    #[default]
+    Plain,
+    User,
+    Assistant,
+    Tool,
+    Thinking,
+    Error,
+}
+
+impl DetailRole {
+    /// Key in the `detail_colors` config.
+    fn config_key(self) -> &'static str {
+        match self {
+            Self::Plain => "",
+            Self::User => "user",
+            Self::Assistant => "assistant",
+            Self::Tool => "tool",
+            Self::Thinking => "thinking",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Appends `text` one screen line at a time, every line tagged with `role`.
+fn push_detail(lines: &mut Vec<(DetailRole, String)>, role: DetailRole, text: &str) {
+    lines.extend(text.split('\n').map(|line| (role, line.to_string())));
+}
+
+/// Assistant turns made only of tool calls, held back while collapsing so a
+/// run spanning several turns renders as one line.
+#[derive(Default)]
+struct ToolRun {
+    /// `Claude (model)` prefix of the first turn in the run.
+    header: String,
+    names: Vec<String>,
+    /// The turns as they would render uncollapsed, used for a lone call.
+    lines: Vec<(DetailRole, String)>,
+}
+
+impl ToolRun {
+    fn flush(&mut self, lines: &mut Vec<(DetailRole, String)>) {
+        if self.names.len() > 1 {
+            lines.push((
+                DetailRole::Tool,
+                format!("{}: {}", self.header, collapsed_tools_summary(&self.names)),
+            ));
+            lines.push((DetailRole::Plain, String::new()));
+        } else {
+            lines.append(&mut self.lines);
+        }
+        *self = Self::default();
+    }
+}
+
 fn render_conversation(
     store: &SessionStore,
     session: &SessionInfo,
-    thinking: bool,
-    tail: Option<usize>,
+    options: RenderOptions,
 ) -> Vec<String> {
+    render_conversation_lines(store, session, options)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// The conversation as screen lines, each tagged with the part of the
+/// conversation it came from so the TUI never has to guess from the text.
+fn render_conversation_lines(
+    store: &SessionStore,
+    session: &SessionInfo,
+    options: RenderOptions,
+) -> Vec<(DetailRole, String)> {
     let assistant_label = if session.source == SessionSource::Codex {
         "Codex"
     } else {
@@ -1868,115 +3896,388 @@ fn render_conversation(
     lines.push(format!("Session: {}", truncate(&session.display, 120)));
     lines.push(format!("Source: {}", session.source.list_label()));
     lines.push(format!("Session ID (full): {}", session.session_id));
+    let absolute = display_time(session.timestamp)
+        .filter(|_| session.timestamp > 0)
+        .map(|dt| format!(" ({})", dt.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
     lines.push(format!(
-        "{}  ·  {}",
+        "{}  ·  {}{absolute}",
         short_project(&session.project),
         relative_time(session.timestamp),
     ));
-    lines.push(String::new());
 
-    let mut msgs = store.read_messages(session, true);
-    msgs.reverse();
-    if let Some(t) = tail {
-        msgs.truncate(t);
+    let mut msgs = store.read_last_messages(session, !options.show_internal, options.tail);
+    // A tail only covers the end of the session, so its span isn't the duration.
+    let duration = conversation_duration_ms(&msgs)
+        .filter(|_| options.tail.is_none())
+        .map(|ms| format!("  ·  ran {}", format_duration(ms)))
+        .unwrap_or_default();
+    if let Some(project_line) = lines.last_mut() {
+        project_line.push_str(&duration);
+    }
+    lines.push(conversation_size_line(&msgs, options.tail.is_some()));
+    if options.stats {
+        lines.push(message_breakdown_line(&store.read_messages(session, false)));
+    }
+    lines.push(String::new());
+    let mut lines: Vec<(DetailRole, String)> = lines
+        .into_iter()
+        .map(|line| (DetailRole::Plain, line))
+        .collect();
+    let tool_results: HashMap<String, String> = msgs
+        .iter()
+        .flat_map(Message::content_blocks)
+        .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_result"))
+        .filter_map(|block| {
+            let id = block
+                .get("tool_use_id")
+                .and_then(Value::as_str)?
+                .to_string();
+            Some((id, tool_result_preview(&block)?))
+        })
+        .collect();
+    if let Some(h) = options.head {
+        msgs.truncate(h);
+    }
+    if !options.reverse {
+        msgs.reverse();
     }
 
+    let collapse = options.collapse_tools && !options.tools_only;
+    let mut tool_run = ToolRun::default();
     for msg in msgs {
         if msg.msg_type == "system" {
             continue;
         }
 
+        if INTERNAL_TYPES.contains(&msg.msg_type.as_str()) {
+            tool_run.flush(&mut lines);
+            lines.push((DetailRole::Plain, format!("[internal: {}]", msg.msg_type)));
+            lines.push((DetailRole::Plain, String::new()));
+            continue;
+        }
+
         if msg.msg_type == "user" {
             let text = msg.text();
-            if text.is_empty() {
+            if text.is_empty() || options.tools_only {
                 continue;
             }
             if text.starts_with("<local-command") || text.starts_with("<command-name") {
                 continue;
             }
-            lines.push(format!("You: {text}"));
-            lines.push(String::new());
+            tool_run.flush(&mut lines);
+            let turn = match turn_time(&msg).filter(|_| options.timestamps) {
+                Some(time) => format!("You ({time}): {text}"),
+                None => format!("You: {text}"),
+            };
+            push_detail(&mut lines, DetailRole::User, &turn);
+            lines.push((DetailRole::Plain, String::new()));
             continue;
         }
 
         if msg.msg_type == "assistant" {
             if msg.is_api_error {
-                lines.push(format!("Error: {}", truncate(&msg.text(), 500)));
-                lines.push(String::new());
+                tool_run.flush(&mut lines);
+                let error = format!("Error: {}", clip(&msg.text(), 500, options.no_truncate));
+                push_detail(&mut lines, DetailRole::Error, &error);
+                lines.push((DetailRole::Plain, String::new()));
                 continue;
             }
 
-            let mut parts: Vec<String> = Vec::new();
+            // Tool calls carry their tool name so runs of them can be collapsed.
+            let mut parts: Vec<TurnPart> = Vec::new();
             for block in msg.content_blocks() {
                 let btype = block.get("type").and_then(Value::as_str).unwrap_or("");
+                if options.tools_only && btype != "tool_use" {
+                    continue;
+                }
                 if matches!(btype, "text" | "input_text" | "output_text") {
                     let text = block_text(&block).unwrap_or_default();
                     if !text.trim().is_empty() {
-                        parts.push(text);
+                        parts.push((None, DetailRole::Assistant, text));
                     }
-                } else if btype == "tool_use" {
+                } else if btype == "tool_use" && !options.no_tools {
                     let name = block.get("name").and_then(Value::as_str).unwrap_or("?");
                     let input = block.get("input").unwrap_or(&Value::Null);
-                    let summary = match name {
-                        "Bash" => {
-                            let cmd = input.get("command").and_then(Value::as_str).unwrap_or("");
-                            let desc = input
-                                .get("description")
-                                .and_then(Value::as_str)
-                                .unwrap_or("");
-                            let detail = if desc.is_empty() { cmd } else { desc };
-                            format!("$ {}", truncate(detail, 80))
-                        }
-                        "Read" | "Edit" | "Write" | "Glob" | "Grep" => {
-                            let target = input
-                                .get("file_path")
-                                .or_else(|| input.get("pattern"))
-                                .and_then(Value::as_str)
-                                .unwrap_or("");
-                            format!("{name} {target}")
-                        }
-                        "Task" => {
-                            let desc = input
-                                .get("description")
-                                .and_then(Value::as_str)
-                                .unwrap_or("");
-                            format!("Task {desc}")
-                        }
-                        "WebSearch" => {
-                            let query = input.get("query").and_then(Value::as_str).unwrap_or("");
-                            format!("Search: {query}")
-                        }
-                        _ => format!("{name}(...)"),
+                    let summary = tool_use_summary(name, input, options.no_truncate);
+                    let result = block
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .and_then(|id| tool_results.get(id));
+                    let text = match result {
+                        Some(result) => format!("[tool] {summary}\n    ↳ {result}"),
+                        None => format!("[tool] {summary}"),
                     };
-                    parts.push(format!("[tool] {summary}"));
-                } else if btype == "thinking" && thinking {
+                    parts.push((Some(name.to_string()), DetailRole::Tool, text));
+                } else if btype == "thinking" && options.thinking {
                     let thinking = block.get("thinking").and_then(Value::as_str).unwrap_or("");
                     if !thinking.trim().is_empty() {
-                        parts.push(format!("[thinking] {}", truncate(thinking, 250)));
+                        parts.push((
+                            None,
+                            DetailRole::Thinking,
+                            format!("[thinking] {}", clip(thinking, 250, options.no_truncate)),
+                        ));
                     }
                 }
             }
 
+            if options.tools_only {
+                for (_, role, text) in parts {
+                    push_detail(&mut lines, role, &text);
+                }
+                continue;
+            }
+
             if !parts.is_empty() {
-                let model = msg.model();
-                if model.is_empty() {
-                    lines.push(format!("{assistant_label}: {}", parts.join("\n")));
-                } else if model != "<synthetic>" {
-                    lines.push(format!("{assistant_label} ({model}): {}", parts.join("\n")));
-                } else {
-                    lines.push(format!("{assistant_label}: {}", parts.join("\n")));
+                let model = Some(msg.model()).filter(|m| !m.is_empty() && *m != "<synthetic>");
+                let time = turn_time(&msg).filter(|_| options.timestamps);
+                let detail = match (model, time) {
+                    (Some(model), Some(time)) => format!(" ({model}, {time})"),
+                    (Some(model), None) => format!(" ({model})"),
+                    (None, Some(time)) => format!(" ({time})"),
+                    (None, None) => String::new(),
+                };
+                let header = format!("{assistant_label}{detail}");
+                if collapse && parts.iter().all(|(name, _, _)| name.is_some()) {
+                    if tool_run.names.is_empty() {
+                        tool_run.header.clone_from(&header);
+                    }
+                    push_turn_parts(&mut tool_run.lines, &header, &parts);
+                    tool_run
+                        .names
+                        .extend(parts.into_iter().filter_map(|(name, _, _)| name));
+                    continue;
                 }
-                lines.push(String::new());
+                tool_run.flush(&mut lines);
+                let parts = if collapse {
+                    collapse_tool_parts(parts)
+                } else {
+                    parts
+                };
+                push_turn_parts(&mut lines, &header, &parts);
             }
         }
     }
+    tool_run.flush(&mut lines);
 
+    if options.strip_ansi {
+        for (_, line) in &mut lines {
+            *line = strip_ansi(line);
+        }
+    }
     lines
 }
 
-fn render_search_results(results: Vec<(SessionInfo, Message, String)>) -> String {
-    if results.is_empty() {
-        return "No matches found.\n".to_string();
+/// An assistant turn: `header: ` before the first part, each part styled by
+/// its own role, then a blank separator.
+fn push_turn_parts(lines: &mut Vec<(DetailRole, String)>, header: &str, parts: &[TurnPart]) {
+    for (idx, (_, role, text)) in parts.iter().enumerate() {
+        if idx == 0 {
+            push_detail(lines, *role, &format!("{header}: {text}"));
+        } else {
+            push_detail(lines, *role, text);
+        }
+    }
+    lines.push((DetailRole::Plain, String::new()));
+}
+
+/// Removes ANSI escape sequences: CSI (colors, cursor moves), OSC (titles,
+/// hyperlinks), and two-byte escapes.
+fn strip_ansi(text: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    if !text.contains('\x1b') {
+        return text.to_string();
+    }
+    let ansi = ANSI.get_or_init(|| {
+        Regex::new(r"\x1b(?:\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[@-Z\\-_])")
+            .expect("valid ANSI regex")
+    });
+    ansi.replace_all(text, "").into_owned()
+}
+
+/// When a message was recorded, in the display zone (`2026-02-13 14:02:11`).
+fn turn_time(msg: &Message) -> Option<String> {
+    codex_iso_to_ms(&msg.timestamp)
+        .and_then(display_time)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// `Messages: 42  ·  ~3,100 words (~14 min read)` for the view header.
+/// With `--tail` only the loaded messages are counted.
+fn conversation_size_line(msgs: &[Message], tail: bool) -> String {
+    let turns: Vec<&Message> = msgs
+        .iter()
+        .filter(|msg| msg.is_conversation_turn())
+        .collect();
+    let words: usize = turns
+        .iter()
+        .map(|msg| msg.text().split_whitespace().count())
+        .sum();
+    let minutes = words.div_ceil(230).max(1);
+    let scope = if tail { " (tail)" } else { "" };
+    format!(
+        "Messages: {}{scope}  ·  ~{} words (~{minutes} min read)",
+        turns.len(),
+        format_with_commas(words as u64)
+    )
+}
+
+/// Counts of each kind of transcript entry: turns by type, tool calls and
+/// thinking blocks, API errors, and internal or other entry types.
+fn message_breakdown_line(msgs: &[Message]) -> String {
+    let mut types: BTreeMap<&str, usize> = BTreeMap::new();
+    let (mut tool_calls, mut thinking, mut api_errors) = (0, 0, 0);
+    for msg in msgs {
+        *types.entry(msg.msg_type.as_str()).or_default() += 1;
+        if msg.is_api_error {
+            api_errors += 1;
+        }
+        for block in msg.content_blocks() {
+            match block.get("type").and_then(Value::as_str) {
+                Some("tool_use") => tool_calls += 1,
+                Some("thinking") => thinking += 1,
+                _ => {}
+            }
+        }
+    }
+    let mut parts = vec![
+        format!("{} user", types.remove("user").unwrap_or(0)),
+        format!("{} assistant", types.remove("assistant").unwrap_or(0)),
+        format!("{tool_calls} tool calls"),
+        format!("{thinking} thinking"),
+        format!("{api_errors} API errors"),
+    ];
+    let internal: Vec<String> = INTERNAL_TYPES
+        .iter()
+        .filter_map(|kind| types.remove(kind).map(|count| format!("{count} {kind}")))
+        .collect();
+    if !internal.is_empty() {
+        parts.push(format!("internal: {}", internal.join(", ")));
+    }
+    let other: Vec<String> = types
+        .into_iter()
+        .map(|(kind, count)| {
+            let kind = if kind.is_empty() { "untyped" } else { kind };
+            format!("{count} {kind}")
+        })
+        .collect();
+    if !other.is_empty() {
+        parts.push(format!("other: {}", other.join(", ")));
+    }
+    format!("Breakdown: {}", parts.join("  ·  "))
+}
+
+/// Time between the first and last timestamped message; `None` unless at
+/// least two messages carry a timestamp.
+fn conversation_duration_ms(msgs: &[Message]) -> Option<i64> {
+    let mut times = msgs
+        .iter()
+        .filter_map(|msg| codex_iso_to_ms(&msg.timestamp));
+    let first = times.next()?;
+    let second = times.next()?;
+    let (lo, hi) = times.fold((first.min(second), first.max(second)), |(lo, hi), ts| {
+        (lo.min(ts), hi.max(ts))
+    });
+    Some(hi - lo)
+}
+
+/// `<1m`, `42m`, `3h05m`, or `2d4h`.
+fn format_duration(ms: i64) -> String {
+    let mins = ms.max(0) / 60_000;
+    match mins {
+        0 => "<1m".to_string(),
+        1..=59 => format!("{mins}m"),
+        60..=1439 => format!("{}h{:02}m", mins / 60, mins % 60),
+        _ => format!("{}d{}h", mins / 1440, mins % 1440 / 60),
+    }
+}
+
+fn render_conversation_json(
+    store: &SessionStore,
+    session: &SessionInfo,
+    options: RenderOptions,
+) -> Value {
+    let mut msgs = store.read_last_messages(session, !options.show_internal, options.tail);
+    if let Some(h) = options.head {
+        msgs.truncate(h);
+    }
+    // Same order as the text view: newest first unless `--reverse`.
+    if !options.reverse {
+        msgs.reverse();
+    }
+
+    let entries: Vec<Value> = msgs
+        .iter()
+        .filter(|msg| matches!(msg.msg_type.as_str(), "user" | "assistant"))
+        .filter_map(|msg| {
+            let tool_calls: Vec<Value> = msg
+                .content_blocks()
+                .iter()
+                .filter(|_| !options.no_tools)
+                .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_use"))
+                .map(|block| {
+                    let name = block.get("name").and_then(Value::as_str).unwrap_or("?");
+                    let input = block.get("input").unwrap_or(&Value::Null);
+                    json!({
+                        "name": name,
+                        "input_summary": tool_use_summary(name, input, options.no_truncate),
+                    })
+                })
+                .collect();
+            let text = if options.tools_only {
+                String::new()
+            } else if options.strip_ansi {
+                strip_ansi(&msg.text())
+            } else {
+                msg.text()
+            };
+            if text.is_empty() && tool_calls.is_empty() {
+                return None;
+            }
+            let model = msg.model();
+            Some(json!({
+                "role": msg.role(),
+                "model": if model.is_empty() { Value::Null } else { json!(model) },
+                "timestamp": msg.timestamp,
+                "text": text,
+                "tool_calls": tool_calls,
+            }))
+        })
+        .collect();
+    json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "session_id": session.session_id,
+        "source": session.source.label(),
+        "messages": entries,
+    })
+}
+
+fn render_history_entries(
+    source: SessionSource,
+    session_id: &str,
+    entries: &[(i64, String)],
+) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "{} history for {session_id} ({} prompt(s))\n\n",
+        source.label(),
+        entries.len()
+    ));
+    for (ts, display) in entries {
+        let absolute = display_time(*ts)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "—".to_string());
+        out.push_str(&format!(
+            "{absolute}  {:>7}  {display}\n",
+            relative_time(*ts)
+        ));
+    }
+    out
+}
+
+fn render_search_results(results: Vec<(SessionInfo, Message, String)>) -> String {
+    if results.is_empty() {
+        return "No matches found.\n".to_string();
     }
 
     let mut out = String::new();
@@ -1987,14 +4288,23 @@ fn render_search_results(results: Vec<(SessionInfo, Message, String)>) -> String
         } else {
             "Claude"
         };
+        let role = msg.role();
+        // An assistant match names the model that actually said it.
+        let model = Some(msg.model())
+            .filter(|model| role != "user" && !model.is_empty())
+            .unwrap_or(session.model.as_str());
+        let model = match (model, session.reasoning_effort.as_str()) {
+            ("", _) => String::new(),
+            (model, "") => format!("  {model}"),
+            (model, effort) => format!("  {model} ({effort})"),
+        };
         out.push_str(&format!(
-            "{}  {}  {}\n",
+            "{}  {}  {}{model}\n",
             session.short_id(),
             relative_time(session.timestamp),
             short_project(&session.project)
         ));
         out.push_str(&format!("  {}\n", truncate(&session.display, 80)));
-        let role = msg.role();
         let role_label = if role == "user" {
             "You"
         } else {
@@ -2005,21 +4315,152 @@ fn render_search_results(results: Vec<(SessionInfo, Message, String)>) -> String
     out
 }
 
-fn render_stats(stats: &StatsReport) -> String {
-    fn render_bar(count: u64, max_count: u64, width: usize) -> String {
+/// Frame, bar, and heatmap characters for `cs stats`.
+struct StatsGlyphs {
+    /// Top-left, top-right, bottom-left, bottom-right corners.
+    corners: [char; 4],
+    horizontal: char,
+    vertical: char,
+    bar: char,
+    /// Heatmap day with no sessions.
+    empty: char,
+    /// Heatmap shades from fewest to most sessions.
+    shades: [char; 4],
+    /// Also swap the `—` and `…` used in values for ASCII.
+    ascii: bool,
+}
+
+impl StatsGlyphs {
+    const UNICODE: Self = Self {
+        corners: ['╭', '╮', '╰', '╯'],
+        horizontal: '─',
+        vertical: '│',
+        bar: '█',
+        empty: '·',
+        shades: ['░', '▒', '▓', '█'],
+        ascii: false,
+    };
+
+    const ASCII: Self = Self {
+        corners: ['+', '+', '+', '+'],
+        horizontal: '-',
+        vertical: '|',
+        bar: '#',
+        empty: '.',
+        shades: [':', '+', '*', '#'],
+        ascii: true,
+    };
+
+    /// ASCII when asked for, or when the environment suggests a terminal
+    /// without Unicode: see `wants_ascii_glyphs`.
+    fn detect(ascii: bool) -> &'static Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty());
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if wants_ascii_glyphs(ascii, locale.as_deref(), no_color) {
+            &Self::ASCII
+        } else {
+            &Self::UNICODE
+        }
+    }
+}
+
+/// Whether `cs stats` should draw with ASCII: when `--ascii` is given, when
+/// the locale is set but isn't UTF-8 (`LANG=C`, `POSIX`, Latin-1 locales),
+/// or when `NO_COLOR` is set.
+fn wants_ascii_glyphs(ascii: bool, locale: Option<&str>, no_color: bool) -> bool {
+    let utf8 = locale.is_none_or(|value| {
+        let value = value.to_lowercase();
+        value.contains("utf-8") || value.contains("utf8")
+    });
+    ascii || !utf8 || no_color
+}
+
+/// `date` minus `days`, saturating at the earliest representable date.
+fn days_before(date: NaiveDate, days: usize) -> NaiveDate {
+    i64::try_from(days)
+        .ok()
+        .and_then(TimeDelta::try_days)
+        .and_then(|offset| date.checked_sub_signed(offset))
+        .unwrap_or(NaiveDate::MIN)
+}
+
+/// Contribution-graph style grid: one column per week (Monday first), one row
+/// per weekday, shaded by that day's count relative to the busiest day.
+fn render_heatmap(
+    daily: &[(String, u64)],
+    end_date: &str,
+    days: usize,
+    glyphs: &StatsGlyphs,
+) -> Vec<String> {
+    let shades = glyphs.shades;
+    let Ok(end) = NaiveDate::parse_from_str(end_date, "%Y-%m-%d") else {
+        return Vec::new();
+    };
+    let start = days_before(end, days.saturating_sub(1));
+    let grid_start = days_before(start, start.weekday().num_days_from_monday() as usize);
+    let weeks = ((end - grid_start).num_days() / 7 + 1) as usize;
+    let counts: HashMap<&str, u64> = daily.iter().map(|(d, c)| (d.as_str(), *c)).collect();
+    let max_count = counts.values().copied().max().unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (weekday, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        let mut row = format!("{label:<4}");
+        for week in 0..weeks {
+            let Some(day) = TimeDelta::try_days((week * 7 + weekday) as i64)
+                .and_then(|offset| grid_start.checked_add_signed(offset))
+            else {
+                break;
+            };
+            if day < start || day > end {
+                row.push(' ');
+                continue;
+            }
+            let count = counts
+                .get(day.format("%Y-%m-%d").to_string().as_str())
+                .copied()
+                .unwrap_or(0);
+            row.push(if count == 0 || max_count == 0 {
+                glyphs.empty
+            } else {
+                let level = (count * shades.len() as u64).div_ceil(max_count) as usize;
+                shades[level.clamp(1, shades.len()) - 1]
+            });
+        }
+        lines.push(row.trim_end().to_string());
+    }
+    lines.push(format!(
+        "    less {}{} more (max {max_count}/day)",
+        glyphs.empty,
+        shades.iter().collect::<String>()
+    ));
+    lines
+}
+
+fn render_stats(stats: &StatsReport, glyphs: &StatsGlyphs) -> String {
+    let render_bar = |count: u64, max_count: u64, width: usize| -> String {
         if max_count == 0 || width == 0 {
             return String::new();
         }
         let n = ((count.saturating_mul(width as u64)) / max_count) as usize;
-        "█".repeat(n.min(width))
-    }
+        glyphs.bar.to_string().repeat(n.min(width))
+    };
 
     let mut out = String::new();
     const FRAME_W: usize = 82;
     let title = "Session Usage Stats (Claude Code + Codex)";
-    out.push_str(&format!("╭{}╮\n", "─".repeat(FRAME_W - 2)));
-    out.push_str(&format!("│{:^width$}│\n", title, width = FRAME_W - 2));
-    out.push_str(&format!("╰{}╯\n\n", "─".repeat(FRAME_W - 2)));
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    let rule = glyphs.horizontal.to_string().repeat(FRAME_W - 2);
+    let side = glyphs.vertical;
+    out.push_str(&format!("{top_left}{rule}{top_right}\n"));
+    out.push_str(&format!(
+        "{side}{:^width$}{side}\n",
+        title,
+        width = FRAME_W - 2
+    ));
+    out.push_str(&format!("{bottom_left}{rule}{bottom_right}\n\n"));
 
     out.push_str(&format!(
         "Total sessions: {}\n",
@@ -2043,6 +4484,26 @@ fn render_stats(stats: &StatsReport) -> String {
             format_with_commas(row.history_entries),
         ));
         out.push_str(&format!("  First session: {}\n", row.first_session_date));
+        if let Some((user_turns, assistant_turns)) = row.turns {
+            let ratio = if user_turns > 0 {
+                format!(
+                    " ({:.1} assistant per user)",
+                    assistant_turns as f64 / user_turns as f64
+                )
+            } else {
+                String::new()
+            };
+            out.push_str(&format!(
+                "  Turns: {} user / {} assistant{ratio}\n",
+                format_with_commas(user_turns),
+                format_with_commas(assistant_turns),
+            ));
+        }
+        if let Some(cost) = row.estimated_cost_usd {
+            out.push_str(&format!(
+                "  Estimated spend: ~${cost:.2} (approximation from message text, ~{CHARS_PER_TOKEN} chars/token)\n"
+            ));
+        }
         out.push('\n');
 
         if row.top_models.is_empty() {
@@ -2066,1040 +4527,5519 @@ fn render_stats(stats: &StatsReport) -> String {
                 .map(|(_, count)| *count)
                 .max()
                 .unwrap_or(1);
-            out.push_str("  Daily sessions (last 14 days):\n");
-            for (date, count) in &row.daily_sessions {
-                let bar = render_bar(*count, max_sessions, 24);
-                out.push_str(&format!(
-                    "    {} {:>6} {}\n",
-                    date,
-                    format_with_commas(*count),
-                    bar
-                ));
+            out.push_str(&format!("  Daily sessions (last {} days):\n", stats.days));
+            if stats.days > 14 {
+                for line in render_heatmap(
+                    &row.daily_sessions,
+                    &stats.last_computed_date,
+                    stats.days,
+                    glyphs,
+                ) {
+                    out.push_str(&format!("    {line}\n"));
+                }
+            } else {
+                for (date, count) in &row.daily_sessions {
+                    let bar = render_bar(*count, max_sessions, 24);
+                    out.push_str(&format!(
+                        "    {} {:>6} {}\n",
+                        date,
+                        format_with_commas(*count),
+                        bar
+                    ));
+                }
             }
             out.push('\n');
         } else {
-            out.push_str("  Daily sessions (last 14 days): —\n\n");
+            out.push_str(&format!(
+                "  Daily sessions (last {} days): —\n\n",
+                stats.days
+            ));
         }
         out.push_str(&format!("{}\n\n", "-".repeat(FRAME_W)));
     }
 
+    if glyphs.ascii {
+        return out.replace('—', "-").replace('…', "...");
+    }
     out
 }
 
-fn list_sessions(sessions: Vec<SessionInfo>, json_output: bool, max_count: usize) -> String {
-    let mut out = String::new();
-    let subset: Vec<_> = sessions.into_iter().take(max_count).collect();
-    let rows: Vec<(SessionInfo, i64)> = subset
-        .into_iter()
-        .map(|s| {
-            let ts_ms = list_time_ms_for_session(&s);
-            (s, ts_ms)
-        })
-        .collect();
+#[derive(Default)]
+struct SearchOptions {
+    project: Option<ProjectFilter>,
+    /// Also match `project` as a subsequence of the project name.
+    project_fuzzy: bool,
+    since_ms: Option<i64>,
+    /// Case-insensitive substring of the session model.
+    model: Option<String>,
+    /// 0 means no limit.
+    max_results: usize,
+    match_mode: Option<MatchMode>,
+    /// Search only this session and report every matching message.
+    session: Option<SessionInfo>,
+}
 
-    if json_output {
-        let data: Vec<_> = rows
-            .into_iter()
-            .map(|(s, _)| {
-                serde_json::json!({
-                    "source": s.source.label(),
-                    "session_id": s.session_id,
-                    "display": s.display,
-                    "project": s.project,
-                    "timestamp": s.timestamp,
-                    "model": s.model,
-                    "reasoning_effort": s.reasoning_effort,
-                    "file_path": s.file_path,
-                })
-            })
-            .collect();
-        let value = serde_json::to_string_pretty(&data).unwrap_or_else(|_| "[]".to_string());
-        return value;
+/// Grouping for `cs list --count-by`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CountBy {
+    Model,
+    Project,
+    Source,
+}
+
+/// Frequency table of `sessions` grouped by `count_by`, busiest first.
+fn render_session_counts(
+    store: &mut SessionStore,
+    sessions: Vec<SessionInfo>,
+    count_by: CountBy,
+) -> String {
+    let total = sessions.len();
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for session in sessions {
+        let key = match count_by {
+            CountBy::Model => store.with_enriched_model(session).model,
+            CountBy::Project => short_project(&session.project),
+            CountBy::Source => session.source.label().to_string(),
+        };
+        let key = if key.trim().is_empty() {
+            "—".to_string()
+        } else {
+            key
+        };
+        *counts.entry(key).or_insert(0) += 1;
     }
+    store.save_cache_if_dirty();
 
-    let source_width = rows
-        .iter()
-        .map(|(s, _)| s.source.list_label().len())
-        .max()
-        .unwrap_or(6)
-        .max("source".len());
-    let time_width = rows
-        .iter()
-        .map(|(_, ts_ms)| list_time(*ts_ms).len())
-        .max()
-        .unwrap_or(4)
-        .max("time".len())
-        .min(7);
-    let project_width = rows
-        .iter()
-        .map(|(s, _)| short_project(&s.project).chars().count().min(32))
-        .max()
-        .unwrap_or(7)
-        .max("project".len());
-    let title_width = rows
+    let mut rows: Vec<(String, u64)> = counts.into_iter().collect();
+    rows.sort_by(|(a_key, a), (b_key, b)| b.cmp(a).then_with(|| a_key.cmp(b_key)));
+    let width = rows
         .iter()
-        .map(|(s, _)| s.display.chars().count().min(48))
+        .map(|(key, _)| key.width())
         .max()
-        .unwrap_or(5)
-        .max("title".len());
-
-    out.push_str(&format!(
-        "{: <source_width$}  {: <5}  {: <time_width$}  {: <project_width$} {:4}  {}\n",
-        "source", "id5", "time", "project", "", "title"
-    ));
-    let line_width =
-        source_width + 2 + 5 + 2 + time_width + 2 + project_width + 1 + 4 + 2 + title_width;
-    out.push_str(&"-".repeat(line_width));
-    out.push('\n');
-    for (s, ts_ms) in rows {
-        let short_id = s.list_id_tail();
-        let time = list_time(ts_ms);
-        let wt = if is_worktree(&s.project) { "[wt]" } else { "" };
-        let proj = truncate(&short_project(&s.project), project_width)
-            .chars()
-            .take(project_width)
-            .collect::<String>();
-        let title = truncate(&s.display, title_width);
+        .unwrap_or(0)
+        .clamp(5, 48);
+    let mut out = String::new();
+    for (key, count) in rows {
         out.push_str(&format!(
-            "{: <source_width$}  {short_id:5}  {time:<time_width$}  {proj:<project_width$} {wt:4}  {title}\n",
-            s.source.list_label()
+            "{}  {:>7}\n",
+            fit_width(&key, width),
+            format_with_commas(count)
         ));
     }
+    out.push_str(&format!(
+        "{}  {:>7}",
+        fit_width("total", width),
+        format_with_commas(total as u64)
+    ));
     out
 }
 
-fn is_view_shortcut(key: &KeyEvent) -> bool {
-    key.modifiers.contains(KeyModifiers::ALT)
-        && matches!(key.code, KeyCode::Char('v') | KeyCode::Char('V'))
+/// How `search` combines whitespace-separated terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum MatchMode {
+    /// Sessions must contain every term (anywhere in the conversation).
+    All,
+    /// Any term matches.
+    Any,
 }
 
-fn open_selected_detail(
-    store: &mut SessionStore,
-    filtered: &[SessionInfo],
-    list_state: &ListState,
-    detail_lines: &mut Vec<String>,
-    in_detail: &mut bool,
-    detail_scroll: &mut usize,
-) {
-    let idx = list_state.selected().unwrap_or_default();
-    if idx >= filtered.len() {
-        return;
-    }
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum ListFormat {
+    #[default]
+    Table,
+    Json,
+    /// One compact JSON object per line, written as sessions are iterated.
+    JsonLines,
+    Csv,
+}
 
-    let selected = &filtered[idx];
-    let session = store
-        .get_exact(selected.source, &selected.session_id)
-        .unwrap_or_else(|| selected.clone());
-    *detail_lines = render_conversation(store, &session, false, None);
-    *in_detail = true;
-    *detail_scroll = 0;
+/// File format for `export-all`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// The rendered conversation, as `cs view` shows it.
+    #[default]
+    Md,
+    /// The `cs view --json` message array.
+    Json,
 }
 
-fn refresh_filter_results(
-    store: &mut SessionStore,
-    filtered: &mut Vec<SessionInfo>,
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Md => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Writes each session to `dir/<project>/<session_id>.<ext>`, where
+/// `<project>` is the project path encoded the way Claude Code names its
+/// project directories. Sessions without a readable, non-empty file, or
+/// whose export can't be written, are reported on stderr and skipped.
+/// Returns (exported, skipped).
+fn export_sessions(
+    store: &SessionStore,
     sessions: &[SessionInfo],
-    previous_filter: &mut String,
-    filter: &str,
-) {
-    let candidate_pool =
-        if !previous_filter.is_empty() && filter.starts_with(previous_filter.as_str()) {
-            filtered.clone()
+    dir: &Path,
+    format: ExportFormat,
+) -> (usize, usize) {
+    // Exports read like documents: oldest turn first, no escape codes.
+    let options = RenderOptions {
+        reverse: true,
+        strip_ansi: true,
+        ..RenderOptions::default()
+    };
+    let (mut exported, mut skipped) = (0, 0);
+    for session in sessions {
+        let Some(file_path) = session.file_path.as_deref() else {
+            eprintln!("cs-rs: skipping {}: no session file", session.session_id);
+            skipped += 1;
+            continue;
+        };
+        if let Err(err) = open_session_file(Path::new(file_path)) {
+            eprintln!("cs-rs: skipping {}: {file_path}: {err}", session.session_id);
+            skipped += 1;
+            continue;
+        }
+        if store.read_messages(session, true).is_empty() {
+            eprintln!("cs-rs: skipping {}: no messages", session.session_id);
+            skipped += 1;
+            continue;
+        }
+        let rendered = match format {
+            ExportFormat::Md => render_conversation(store, session, options).join("\n"),
+            ExportFormat::Json => {
+                let value = render_conversation_json(store, session, options);
+                serde_json::to_string_pretty(&value).unwrap_or_else(|_| "{}".to_string())
+            }
+        };
+        let project_dir = if session.project.is_empty() {
+            "unknown-project".to_string()
         } else {
-            sessions.to_vec()
+            SessionStore::encode_path(&session.project)
         };
-    apply_filter(store, filtered, &candidate_pool, filter);
-    previous_filter.clear();
-    previous_filter.push_str(filter);
+        let path = dir.join(project_dir).join(format!(
+            "{}.{}",
+            export_file_stem(&session.session_id),
+            format.extension()
+        ));
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| write_output_file(&path, &rendered));
+        match written {
+            Ok(()) => exported += 1,
+            Err(err) => {
+                eprintln!("cs-rs: skipping {}: {err:#}", session.session_id);
+                skipped += 1;
+            }
+        }
+    }
+    (exported, skipped)
 }
 
-fn run_tui() -> Result<()> {
-    let mut store = SessionStore::new();
-    let mut sessions = store.all();
-    let list_time_ms_map = build_list_time_ms_map(&sessions);
-    let list_time_by_session = build_list_time_str_map(&list_time_ms_map);
-    sessions.sort_by_cached_key(|s| {
-        Reverse(
-            *list_time_ms_map
-                .get(&s.source.internal_key(&s.session_id))
-                .unwrap_or(&s.timestamp),
-        )
-    });
-    let mut filtered = sessions.clone();
-    let mut list_state = ListState::default();
-    list_state.select(Some(0));
-
-    let mut filter = String::new();
-    let mut previous_filter = String::new();
-    let mut filter_input = false;
-    let mut in_detail = false;
-    let mut detail_lines = Vec::<String>::new();
-    let mut detail_scroll: usize = 0;
-
-    let mut terminal = init_terminal()?;
-
-    loop {
-        terminal.draw(|f| {
-            let size = f.size();
-            let top_height = if in_detail {
-                0u16
+/// `session_id` made safe as a file name: anything but ASCII letters, digits,
+/// `-`, and `_` becomes `_`, so IDs can't climb out of the export directory.
+fn export_file_stem(session_id: &str) -> String {
+    let stem: String = session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_') {
+                ch
             } else {
-                (filter_input as u16) + 1
-            };
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(top_height),
-                    Constraint::Min(1),
-                    Constraint::Length(1),
-                ])
-                .split(size);
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "session".to_string()
+    } else {
+        stem
+    }
+}
 
-            let status = if in_detail {
-                " [↑/↓] scroll  [Esc]/[b] back  [Ctrl-c]/[q] quit"
-            } else {
-                " [↑/↓ or Ctrl-u/Ctrl-d] navigate  [Enter] resume  [Option-v] view  [/] search  [Ctrl-c]/[q] quit"
-            };
+/// Field names accepted by `list --fields`, matching the JSON keys.
+const LIST_FIELDS: &[&str] = &[
+    "source",
+    "session_id",
+    "display",
+    "project",
+    "timestamp",
+    "model",
+    "reasoning_effort",
+    "file_path",
+];
+
+const DEFAULT_CSV_FIELDS: &[&str] = &[
+    "source",
+    "session_id",
+    "project",
+    "timestamp",
+    "model",
+    "reasoning_effort",
+    "file_path",
+];
+
+fn parse_list_fields(spec: &str) -> Result<Vec<String>> {
+    let fields: Vec<String> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+    if let Some(unknown) = fields.iter().find(|f| !LIST_FIELDS.contains(&f.as_str())) {
+        return Err(anyhow!(
+            "Unknown field '{unknown}' (expected one of: {})",
+            LIST_FIELDS.join(", ")
+        ));
+    }
+    if fields.is_empty() {
+        return Err(anyhow!("--fields needs at least one field name"));
+    }
+    Ok(fields)
+}
 
-            if !in_detail {
-                let filter_text = if filter_input {
-                    format!("> {filter}")
-                } else {
-                    "Type to full-text search sessions...".to_string()
-                };
-                f.render_widget(
-                    Paragraph::new(filter_text)
-                        .style(Style::default().fg(Color::White))
-                        .block(Block::default().borders(Borders::BOTTOM)),
-                    chunks[0],
-                );
-            }
+/// CSV cell for one `LIST_FIELDS` entry; timestamps are ISO 8601.
+fn session_csv_cell(s: &SessionInfo, field: &str) -> String {
+    match field {
+        "source" => s.source.label().to_string(),
+        "session_id" => s.session_id.clone(),
+        "display" => s.display.clone(),
+        "project" => s.project.clone(),
+        "timestamp" => display_time(s.timestamp)
+            .filter(|_| s.timestamp > 0)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default(),
+        "model" => s.model.clone(),
+        "reasoning_effort" => s.reasoning_effort.clone(),
+        "file_path" => s.file_path.clone().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
 
-            if in_detail {
-                let line_count = detail_lines.len();
-                let visible = chunks[1].height as usize;
-                let end = detail_lines.len().min(detail_scroll + visible);
-                let slice = if detail_scroll < line_count {
-                    detail_lines[detail_scroll..end].to_vec()
-                } else {
-                    Vec::new()
-                };
-                let lines: Vec<Line> = slice.iter().map(|line| Line::from(line.clone())).collect();
-                f.render_widget(
-                    Paragraph::new(lines)
-                        .block(Block::default().borders(Borders::ALL).title("Session")),
-                    chunks[1],
-                );
-            } else {
-                let items: Vec<ListItem> = filtered
-                    .iter()
-                    .map(|s| {
-                        let time = list_time_by_session
-                            .get(&s.source.internal_key(&s.session_id))
-                            .cloned()
-                            .unwrap_or_default();
-                        let id_tail = s.list_id_tail();
-                        let wt = if is_worktree(&s.project) { "[wt]" } else { "" };
-                        let project = truncate(&short_project(&s.project), 38);
-                        let (size, is_large_size) = file_size_for_session(&s.file_path);
-                        let prompt_w = (chunks[1].width as usize)
-                            .saturating_sub(7 + 3 + 5 + 3 + 5 + 3 + 38 + 1 + 4 + 3 + 8 + 3)
-                            .max(20);
-                        let prompt = truncate(&s.display, prompt_w);
-                        let source = s.source.list_label();
-                        let source_style = if s.source == SessionSource::Codex {
-                            Style::default().fg(Color::Rgb(88, 166, 255))
-                        } else {
-                            // Anthropic-style orange for cc rows.
-                            Style::default().fg(Color::Rgb(217, 119, 87))
-                        };
-                        let size_style = if is_large_size {
-                            Style::default().fg(Color::Red)
-                        } else {
-                            Style::default().fg(Color::DarkGray)
-                        };
-                        let row = Line::from(vec![
-                            Span::styled(
-                                format!("{time:>7}"),
-                                Style::default().fg(Color::DarkGray),
-                            ),
-                            Span::from("   "),
-                            Span::styled(format!("{source:5}"), source_style),
-                            Span::from("   "),
-                            Span::styled(format!("{id_tail:>5}"), Style::default().fg(Color::DarkGray)),
-                            Span::from("   "),
-                            Span::styled(format!("{project:38}"), Style::default().fg(Color::Cyan)),
-                            Span::from(" "),
-                            Span::styled(format!("{wt:4}"), Style::default().fg(Color::Yellow)),
-                            Span::from("   "),
-                            Span::styled(format!("{size:>8}"), size_style),
-                            Span::from("   "),
-                            Span::from(prompt),
-                        ]);
-                        ListItem::new(row)
-                    })
-                    .collect();
+/// JSON object for a session, restricted to `fields` when any are given.
+fn session_json(s: &SessionInfo, fields: &[String]) -> Value {
+    let mut value = json!({
+        "source": s.source.label(),
+        "session_id": s.session_id,
+        "display": s.display,
+        "project": s.project,
+        "timestamp": s.timestamp,
+        "model": s.model,
+        "reasoning_effort": s.reasoning_effort,
+        "file_path": s.file_path,
+    });
+    if let (Value::Object(map), false) = (&mut value, fields.is_empty()) {
+        map.retain(|key, _| fields.contains(key));
+    }
+    value
+}
 
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("Sessions"))
-                    .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan))
-                    .highlight_symbol("> ");
-                f.render_stateful_widget(list, chunks[1], &mut list_state);
-            }
+/// `--limit 0` / `--max 0` mean "no limit".
+fn unlimited_if_zero(limit: usize) -> usize {
+    if limit == 0 {
+        usize::MAX
+    } else {
+        limit
+    }
+}
 
-            f.render_widget(
-                Paragraph::new(status).style(Style::default().fg(Color::White)),
-                chunks[2],
-            );
-        })?;
+/// Streams `--format json-lines` output so large inventories never build one
+/// big array.
+fn write_sessions_json_lines(
+    sessions: &[SessionInfo],
+    max_count: usize,
+    fields: &[String],
+    out: &mut impl Write,
+) -> Result<()> {
+    for session in sessions.iter().take(unlimited_if_zero(max_count)) {
+        serde_json::to_writer(&mut *out, &session_json(session, fields))?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
 
-        if !event::poll(StdDuration::from_secs(60))? {
-            continue;
+/// Quotes a CSV field when it contains a delimiter, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+struct SourceDiagnostics {
+    source: SessionSource,
+    /// Name, resolved path, and whether it exists.
+    paths: Vec<(&'static str, PathBuf, bool)>,
+    counts: LoadCounts,
+    loaded: usize,
+}
+
+/// Resolved locations and load counts printed by `cs doctor`.
+struct DoctorReport {
+    home: PathBuf,
+    data_home: PathBuf,
+    cache_path: PathBuf,
+    cache_status: String,
+    corrupt_backup: Option<PathBuf>,
+    ignore_path: PathBuf,
+    sources: Vec<SourceDiagnostics>,
+}
+
+fn render_doctor_report(report: &DoctorReport) -> String {
+    let mut out = format!("Home:       {}\n", report.home.display());
+    if report.data_home != report.home {
+        out.push_str(&format!(
+            "Data home:  {}  (CS_RS_DATA_DIR)\n",
+            report.data_home.display()
+        ));
+    }
+    out.push_str(&format!(
+        "Cache:      {}  ({})\n",
+        report.cache_path.display(),
+        report.cache_status
+    ));
+    if let Some(backup) = &report.corrupt_backup {
+        out.push_str(&format!(
+            "            an earlier unreadable cache was kept at {}\n",
+            backup.display()
+        ));
+    }
+    for diag in &report.sources {
+        out.push_str(&format!("\n{}\n", diag.source.label()));
+        for (name, path, exists) in &diag.paths {
+            out.push_str(&format!(
+                "  {name:<9} {}  {}\n",
+                if *exists { "ok     " } else { "missing" },
+                path.display()
+            ));
+        }
+        let LoadCounts {
+            in_history,
+            missing_file,
+            ignored,
+        } = diag.counts;
+        out.push_str(&format!(
+            "  {} session(s) loaded of {in_history} in history",
+            diag.loaded
+        ));
+        if missing_file > 0 {
+            out.push_str(&format!("; {missing_file} without a session file on disk"));
         }
+        if ignored > 0 {
+            out.push_str(&format!(
+                "; {ignored} hidden by {}",
+                report.ignore_path.display()
+            ));
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
 
-        let Event::Key(key) = event::read()? else {
-            continue;
-        };
+fn render_orphaned_files(orphans: &[(SessionSource, PathBuf)]) -> String {
+    if orphans.is_empty() {
+        return "No orphaned session files: every transcript on disk is in history.".to_string();
+    }
+    let mut out = format!(
+        "{} session file(s) on disk are missing from history (view them with `cs view <path>`):\n\n",
+        orphans.len()
+    );
+    let source_w = SessionSource::list_label_width();
+    for (source, path) in orphans {
+        let size = fs::metadata(path)
+            .map(|m| human_file_size(m.len()))
+            .unwrap_or_else(|_| "—".to_string());
+        out.push_str(&format!(
+            "{}  {size:>8}  {}\n",
+            fit_width(source.list_label(), source_w),
+            path.display()
+        ));
+    }
+    out.trim_end().to_string()
+}
 
-        if key.kind != KeyEventKind::Press {
-            continue;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SessionAge {
+    /// Active within the last hour.
+    Fresh,
+    /// Within the last day.
+    Recent,
+    Old,
+}
+
+fn session_age(ts_ms: i64) -> SessionAge {
+    let age_ms = Local::now().timestamp_millis() - ts_ms;
+    if age_ms < 3_600_000 {
+        SessionAge::Fresh
+    } else if age_ms < 86_400_000 {
+        SessionAge::Recent
+    } else {
+        SessionAge::Old
+    }
+}
+
+impl SessionAge {
+    fn style(self) -> Style {
+        match self {
+            Self::Fresh => Style::default().fg(Color::Green),
+            Self::Recent => Style::default(),
+            Self::Old => Style::default().fg(Color::DarkGray),
         }
+    }
 
-        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-            break;
+    fn ansi_paint(self, text: &str) -> String {
+        match self {
+            Self::Fresh => format!("\x1b[32m{text}\x1b[0m"),
+            Self::Recent => text.to_string(),
+            Self::Old => format!("\x1b[2m{text}\x1b[0m"),
         }
+    }
+}
 
-        if in_detail {
-            match key.code {
-                KeyCode::Char('q') => break,
-                KeyCode::Esc | KeyCode::Char('b') | KeyCode::Left => {
-                    in_detail = false;
-                    detail_scroll = 0;
-                    detail_lines.clear();
-                }
-                KeyCode::Up => {
-                    detail_scroll = detail_scroll.saturating_sub(1);
-                }
-                KeyCode::Down => {
-                    if detail_scroll + 1 < detail_lines.len() {
-                        detail_scroll += 1;
-                    }
-                }
-                _ => {}
-            }
-            continue;
+/// ANSI color for CLI output: only on a terminal, and never with `NO_COLOR` set.
+fn stdout_color_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Layout and extra columns for the `cs list` table; other formats ignore it.
+#[derive(Clone, Copy, Default)]
+struct TableOptions {
+    /// Omit the header row and separator.
+    compact: bool,
+    /// Append each project's current git branch.
+    show_branch: bool,
+    /// Add a column with how long each session ran.
+    show_duration: bool,
+}
+
+fn list_sessions(
+    sessions: Vec<SessionInfo>,
+    format: ListFormat,
+    max_count: usize,
+    fields: &[String],
+    table: TableOptions,
+    durations: &HashMap<String, i64>,
+) -> String {
+    let mut out = String::new();
+    let subset: Vec<_> = sessions
+        .into_iter()
+        .take(unlimited_if_zero(max_count))
+        .collect();
+    let rows: Vec<(SessionInfo, i64)> = subset
+        .into_iter()
+        .map(|s| {
+            let ts_ms = list_time_ms_for_session(&s);
+            (s, ts_ms)
+        })
+        .collect();
+
+    if format == ListFormat::Csv {
+        let columns: Vec<&str> = if fields.is_empty() {
+            DEFAULT_CSV_FIELDS.to_vec()
+        } else {
+            fields.iter().map(String::as_str).collect()
+        };
+        out.push_str(&columns.join(","));
+        out.push('\n');
+        for (s, _) in rows {
+            let line: Vec<String> = columns
+                .iter()
+                .map(|field| csv_field(&session_csv_cell(&s, field)))
+                .collect();
+            out.push_str(&line.join(","));
+            out.push('\n');
         }
+        return out.trim_end().to_string();
+    }
 
-        if filter_input {
-            if is_view_shortcut(&key) {
-                open_selected_detail(
-                    &mut store,
-                    &filtered,
-                    &list_state,
-                    &mut detail_lines,
-                    &mut in_detail,
-                    &mut detail_scroll,
-                );
-                continue;
+    if format == ListFormat::Json {
+        let data: Vec<_> = rows.iter().map(|(s, _)| session_json(s, fields)).collect();
+        let value = json!({ "schema_version": JSON_SCHEMA_VERSION, "sessions": data });
+        return serde_json::to_string_pretty(&value).unwrap_or_else(|_| "{}".to_string());
+    }
+
+    let source_width = rows
+        .iter()
+        .map(|(s, _)| s.source.list_label().width())
+        .max()
+        .unwrap_or(6)
+        .max("source".len());
+    let time_width = rows
+        .iter()
+        .map(|(_, ts_ms)| list_time(*ts_ms).len())
+        .max()
+        .unwrap_or(4)
+        .max("time".len())
+        .min(7);
+    let mut branches: HashMap<String, Option<String>> = HashMap::new();
+    let projects: Vec<String> = rows
+        .iter()
+        .map(|(s, _)| {
+            let project = short_project(&s.project);
+            if !table.show_branch {
+                return project;
             }
+            let branch = branches
+                .entry(s.project.clone())
+                .or_insert_with(|| git_branch(&s.project));
+            match branch {
+                Some(branch) => format!("{project} ({branch})"),
+                None => project,
+            }
+        })
+        .collect();
+    let project_cap = if table.show_branch { 48 } else { 32 };
+    let project_width = projects
+        .iter()
+        .map(|project| project.width().min(project_cap))
+        .max()
+        .unwrap_or(7)
+        .max("project".len());
+    let title_width = rows
+        .iter()
+        .map(|(s, _)| {
+            let marker = if s.is_archived() {
+                "(archived) ".len()
+            } else {
+                0
+            };
+            (marker + s.display.width()).min(48)
+        })
+        .max()
+        .unwrap_or(5)
+        .max("title".len());
 
-            match key.code {
-                KeyCode::Esc => {
-                    filter_input = false;
-                    filter.clear();
-                    refresh_filter_results(
-                        &mut store,
-                        &mut filtered,
-                        &sessions,
-                        &mut previous_filter,
-                        &filter,
-                    );
-                    list_state.select(Some(0));
-                }
-                KeyCode::Backspace => {
-                    filter.pop();
-                    refresh_filter_results(
-                        &mut store,
-                        &mut filtered,
-                        &sessions,
-                        &mut previous_filter,
-                        &filter,
-                    );
-                    list_state.select(Some(0));
-                }
-                KeyCode::Up => {
-                    let prev = match list_state.selected() {
-                        Some(0) | None => 0,
-                        Some(i) => i.saturating_sub(1),
-                    };
-                    list_state.select(Some(prev));
-                }
-                KeyCode::Down => {
-                    let len = filtered.len();
-                    let next = match list_state.selected() {
-                        Some(i) => {
-                            if i + 1 < len {
-                                i + 1
-                            } else {
-                                i
-                            }
-                        }
-                        None => 0,
-                    };
-                    list_state.select(Some(next));
-                }
-                KeyCode::Enter => {
-                    let idx = list_state.selected().unwrap_or_default();
-                    if idx < filtered.len() {
-                        let selected = &filtered[idx];
-                        let session = store
-                            .get_exact(selected.source, &selected.session_id)
-                            .unwrap_or_else(|| selected.clone());
-                        cleanup_terminal(&mut terminal)?;
-                        resume_session(&session)?;
-                        return Ok(());
-                    }
-                }
-                KeyCode::Char(c) => {
-                    if !c.is_control() && key.modifiers.is_empty() {
-                        filter.push(c);
-                    }
-                    refresh_filter_results(
-                        &mut store,
-                        &mut filtered,
-                        &sessions,
-                        &mut previous_filter,
-                        &filter,
-                    );
-                    list_state.select(Some(0));
-                }
-                _ => {}
-            }
-            continue;
+    let duration_cells: Vec<String> = rows
+        .iter()
+        .map(|(s, _)| {
+            durations
+                .get(&s.source.internal_key(&s.session_id))
+                .map(|ms| format_duration(*ms))
+                .unwrap_or_else(|| "-".to_string())
+        })
+        .collect();
+    let duration_width = if table.show_duration {
+        duration_cells
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max("ran".len())
+    } else {
+        0
+    };
+    let duration_column = |cell: &str| {
+        if table.show_duration {
+            format!("{cell:>duration_width$}  ")
+        } else {
+            String::new()
         }
+    };
 
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('u') => {
-                    let prev = match list_state.selected() {
-                        Some(0) | None => 0,
-                        Some(i) => i.saturating_sub(1),
-                    };
-                    list_state.select(Some(prev));
-                    continue;
-                }
-                KeyCode::Char('d') => {
-                    let len = filtered.len();
-                    let next = match list_state.selected() {
-                        Some(i) => {
-                            if i + 1 < len {
-                                i + 1
-                            } else {
-                                i
-                            }
-                        }
-                        None => 0,
-                    };
-                    list_state.select(Some(next));
-                    continue;
-                }
-                _ => {}
+    let row_width = rows.len().to_string().len();
+    if !table.compact {
+        out.push_str(&format!(
+            "{:>row_width$}  {: <source_width$}  {: <5}  {: <time_width$}  {}{: <project_width$} {:4}  {}\n",
+            "#",
+            "source",
+            "id5",
+            "time",
+            duration_column("ran"),
+            "project",
+            "",
+            "title"
+        ));
+        let duration_span = if table.show_duration {
+            duration_width + 2
+        } else {
+            0
+        };
+        let line_width = row_width
+            + 2
+            + source_width
+            + 2
+            + 5
+            + 2
+            + time_width
+            + 2
+            + duration_span
+            + project_width
+            + 1
+            + 4
+            + 2
+            + title_width;
+        out.push_str(&"-".repeat(line_width));
+        out.push('\n');
+    }
+    let color = stdout_color_enabled();
+    let numbered = rows.into_iter().enumerate();
+    for (((row, (s, ts_ms)), project), duration) in numbered.zip(projects).zip(duration_cells) {
+        let short_id = s.list_id_tail();
+        let time = format!("{:<time_width$}", list_time(ts_ms));
+        let time = if color {
+            session_age(ts_ms).ansi_paint(&time)
+        } else {
+            time
+        };
+        let wt = if is_worktree(&s.project) { "[wt]" } else { "" };
+        let source = fit_width(s.source.list_label(), source_width);
+        let proj = fit_width(&project, project_width);
+        let title = if s.is_archived() {
+            fit_width(&format!("(archived) {}", s.display), title_width)
+        } else {
+            fit_width(&s.display, title_width)
+        };
+        out.push_str(&format!(
+            "{:>row_width$}  {source}  {short_id:5}  {time}  {}{proj} {wt:4}  {}\n",
+            row + 1,
+            duration_column(&duration),
+            title.trim_end()
+        ));
+    }
+    out
+}
+
+/// The branch currently checked out in `project`, or `None` for missing
+/// directories, non-git projects, and detached HEADs.
+fn git_branch(project: &str) -> Option<String> {
+    if project.is_empty() || !Path::new(project).is_dir() {
+        return None;
+    }
+    let output = Command::new("git")
+        .args(["-C", project, "rev-parse", "--abbrev-ref", "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty() && branch != "HEAD").then_some(branch)
+}
+
+fn is_view_shortcut(key: &KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::ALT)
+        && matches!(key.code, KeyCode::Char('v') | KeyCode::Char('V'))
+}
+
+fn open_selected_detail(
+    store: &mut SessionStore,
+    filtered: &[SessionInfo],
+    list_state: &ListState,
+    detail: &mut DetailLines,
+    in_detail: &mut bool,
+    detail_scroll: &mut usize,
+    options: RenderOptions,
+) {
+    let idx = list_state.selected().unwrap_or_default();
+    if idx >= filtered.len() {
+        return;
+    }
+
+    let selected = &filtered[idx];
+    let session = store
+        .get_exact(selected.source, &selected.session_id)
+        .unwrap_or_else(|| selected.clone());
+    let (roles, lines) = render_conversation_lines(store, &session, options)
+        .into_iter()
+        .unzip();
+    *detail = DetailLines { lines, roles };
+    *in_detail = true;
+    *detail_scroll = 0;
+}
+
+/// The detail view's screen lines, with the conversation role of each.
+#[derive(Default)]
+struct DetailLines {
+    lines: Vec<String>,
+    roles: Vec<DetailRole>,
+}
+
+fn detail_role_style(role: DetailRole) -> Style {
+    let default = match role {
+        DetailRole::User => Color::Cyan,
+        DetailRole::Assistant => Color::White,
+        DetailRole::Tool => Color::Yellow,
+        DetailRole::Thinking => Color::DarkGray,
+        DetailRole::Error => Color::Red,
+        DetailRole::Plain => return Style::default(),
+    };
+    Style::default().fg(config().detail_color(role.config_key(), default))
+}
+
+/// Styles `detail.lines[start..end]` by the role each line was rendered with.
+fn styled_detail_lines(detail: &DetailLines, start: usize, end: usize) -> Vec<Line<'static>> {
+    detail.lines[start..end]
+        .iter()
+        .zip(&detail.roles[start..end])
+        .map(|(line, role)| Line::styled(line.clone(), detail_role_style(*role)))
+        .collect()
+}
+
+/// Index of the turn (blank-line separated block) containing line `scroll`.
+fn detail_turn_at(detail_lines: &[String], scroll: usize) -> usize {
+    detail_lines
+        .iter()
+        .take(scroll)
+        .filter(|line| line.is_empty())
+        .count()
+}
+
+/// First line of turn `turn`, so a re-render can keep the reader's place.
+fn detail_turn_start(detail_lines: &[String], turn: usize) -> usize {
+    if turn == 0 {
+        return 0;
+    }
+    detail_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.is_empty())
+        .nth(turn - 1)
+        .map_or(detail_lines.len().saturating_sub(1), |(idx, _)| idx + 1)
+}
+
+fn detail_match_lines(detail_lines: &[String], filter: &str) -> Vec<usize> {
+    let q = filter.to_lowercase();
+    if q.is_empty() {
+        return Vec::new();
+    }
+    detail_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.to_lowercase().contains(&q))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn jump_to_detail_match(
+    matches: &[usize],
+    detail_scroll: &mut usize,
+    detail_match: &mut Option<usize>,
+    forward: bool,
+) {
+    if matches.is_empty() {
+        *detail_match = None;
+        return;
+    }
+
+    let next = match *detail_match {
+        Some(current) if forward => (current + 1) % matches.len(),
+        Some(current) => (current + matches.len() - 1) % matches.len(),
+        None if forward => matches
+            .iter()
+            .position(|line| *line >= *detail_scroll)
+            .unwrap_or(0),
+        None => matches
+            .iter()
+            .rposition(|line| *line <= *detail_scroll)
+            .unwrap_or(matches.len() - 1),
+    };
+    *detail_match = Some(next);
+    *detail_scroll = matches[next];
+}
+
+/// Scrolls a freshly opened detail view to the first search hit in the
+/// conversation body (skipping the header, whose title often matches too).
+fn land_on_first_match(
+    detail_lines: &[String],
+    filter: &str,
+    detail_scroll: &mut usize,
+    detail_match: &mut Option<usize>,
+) {
+    let matches = detail_match_lines(detail_lines, filter);
+    let body_start = detail_lines
+        .iter()
+        .position(|line| line.is_empty())
+        .map_or(0, |idx| idx + 1);
+    let Some(first) = matches
+        .iter()
+        .position(|line| *line >= body_start)
+        .or_else(|| (!matches.is_empty()).then_some(0))
+    else {
+        return;
+    };
+    *detail_match = Some(first);
+    *detail_scroll = matches[first];
+}
+
+/// Which keys the TUI is listening for, for the `?` help overlay.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TuiMode {
+    List,
+    Filter,
+    Detail,
+}
+
+impl TuiMode {
+    fn label(self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Filter => "search",
+            Self::Detail => "session",
+        }
+    }
+
+    /// (keys, action) rows shown in the help overlay.
+    fn keybindings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::List => &[
+                ("↑/↓, Ctrl-u/Ctrl-d", "move the selection"),
+                ("Enter", "resume the session"),
+                ("→, Option-v", "view the conversation"),
+                ("/", "full-text search"),
+                ("t", "add or remove a tag"),
+                ("b", "show tagged sessions only / all"),
+                ("P", "pin or unpin the session"),
+                ("o", "reveal the session file"),
+                ("s", "cycle the sort order"),
+                ("?, F1", "toggle this help"),
+                ("q, Esc, Ctrl-c", "quit"),
+            ],
+            Self::Filter => &[
+                ("type", "filter by title, project, ID, or text"),
+                ("Backspace", "delete a character"),
+                ("↑/↓", "move the selection"),
+                ("Enter", "resume the session"),
+                ("Option-v", "view the conversation"),
+                ("Esc", "clear the search"),
+                ("F1", "toggle this help"),
+                ("Ctrl-c", "quit"),
+            ],
+            Self::Detail => &[
+                ("↑/↓", "scroll"),
+                ("n / N", "next / previous search match"),
+                ("T", "show or hide thinking"),
+                ("s", "show or hide turn timestamps"),
+                ("c", "copy the conversation"),
+                ("o", "reveal the session file"),
+                ("Esc, b, ←", "back to the list"),
+                ("?, F1", "toggle this help"),
+                ("q, Ctrl-c", "quit"),
+            ],
+        }
+    }
+}
+
+/// A `width` x `height` rectangle centered in `area`, shrunk to fit.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn render_help_overlay(f: &mut ratatui::Frame, mode: TuiMode) {
+    let rows = mode.keybindings();
+    let key_width = rows.iter().map(|(keys, _)| keys.width()).max().unwrap_or(0);
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|(keys, action)| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {keys:key_width$}  "),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::from(*action),
+            ])
+        })
+        .collect();
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 3;
+    let area = centered_rect(width, rows.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Keys: {} (? or Esc to close) ", mode.label())),
+        ),
+        area,
+    );
+}
+
+/// Quiet period after a filter keystroke before the (possibly full-text) scan
+/// runs, so fast typing scans once instead of once per key.
+const FILTER_DEBOUNCE: StdDuration = StdDuration::from_millis(150);
+
+fn refresh_filter_results(
+    store: &mut SessionStore,
+    filtered: &mut Vec<SessionInfo>,
+    sessions: &[SessionInfo],
+    previous_filter: &mut String,
+    filter: &str,
+    tagged_only: bool,
+) {
+    let candidate_pool =
+        if !previous_filter.is_empty() && filter.starts_with(previous_filter.as_str()) {
+            filtered.clone()
+        } else {
+            sessions.to_vec()
+        };
+    apply_filter(store, filtered, &candidate_pool, filter);
+    if tagged_only {
+        filtered.retain(|session| !store.session_tags(session).is_empty());
+    }
+    previous_filter.clear();
+    previous_filter.push_str(filter);
+}
+
+/// Time spent matching sessions before the TUI checks for input again while a
+/// filter scan runs, so typing and Esc stay responsive during full-text scans.
+const FILTER_SCAN_BUDGET: StdDuration = StdDuration::from_millis(25);
+
+/// A TUI filter applied a little at a time: `pool` is checked in order, one
+/// `FILTER_SCAN_BUDGET` per tick, and matches are appended to the list as
+/// they are found.
+struct FilterScan {
+    query: String,
+    pool: Vec<SessionInfo>,
+    next: usize,
+    tagged_only: bool,
+    /// Selection from before the scan, restored once that session matches
+    /// (unless the user has moved on).
+    restore_key: Option<String>,
+}
+
+impl FilterScan {
+    /// Clears `filtered` and starts matching `filter`. Narrowing a query whose
+    /// scan finished only rescans its matches. `previous_filter` is cleared
+    /// until this scan finishes.
+    fn start(
+        filtered: &mut Vec<SessionInfo>,
+        sessions: &[SessionInfo],
+        previous_filter: &mut String,
+        filter: &str,
+        tagged_only: bool,
+        restore_key: Option<String>,
+    ) -> Self {
+        let pool = if !previous_filter.is_empty() && filter.starts_with(previous_filter.as_str()) {
+            std::mem::take(filtered)
+        } else {
+            filtered.clear();
+            sessions.to_vec()
+        };
+        previous_filter.clear();
+        Self {
+            query: filter.to_lowercase(),
+            pool,
+            next: 0,
+            tagged_only,
+            restore_key,
+        }
+    }
+
+    /// Matches sessions until `budget` is used up (at least one per call),
+    /// appending hits to `filtered`. Returns true once the pool is exhausted.
+    fn step(
+        &mut self,
+        store: &mut SessionStore,
+        filtered: &mut Vec<SessionInfo>,
+        budget: StdDuration,
+    ) -> bool {
+        let started = Instant::now();
+        while let Some(session) = self.pool.get(self.next) {
+            self.next += 1;
+            if session_matches_filter(store, session, &self.query)
+                && (!self.tagged_only || !store.session_tags(session).is_empty())
+            {
+                filtered.push(session.clone());
+            }
+            if started.elapsed() >= budget {
+                break;
             }
         }
+        self.next >= self.pool.len()
+    }
+}
 
-        if key.code == KeyCode::Right || is_view_shortcut(&key) {
-            open_selected_detail(
-                &mut store,
-                &filtered,
-                &list_state,
-                &mut detail_lines,
-                &mut in_detail,
-                &mut detail_scroll,
-            );
-            continue;
+/// Session list orderings; `s` cycles through them in the TUI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum SessionSort {
+    /// Most recently active first.
+    #[default]
+    Recent,
+    Oldest,
+    /// By project path, most recent first within a project.
+    Project,
+    /// By model name, most recent first within a model.
+    Model,
+    /// Most recently resumed through cs-rs first; never-resumed sessions
+    /// follow, most recent first.
+    Resumed,
+}
+
+impl SessionSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Recent => Self::Oldest,
+            Self::Oldest => Self::Project,
+            Self::Project => Self::Model,
+            Self::Model => Self::Resumed,
+            Self::Resumed => Self::Recent,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Recent => "recent",
+            Self::Oldest => "oldest",
+            Self::Project => "project",
+            Self::Model => "model",
+            Self::Resumed => "resumed",
         }
+    }
+}
+
+fn sort_sessions(
+    sessions: &mut [SessionInfo],
+    sort: SessionSort,
+    list_time_ms_map: &HashMap<String, i64>,
+    resumed: &HashMap<String, i64>,
+) {
+    // Every ordering breaks ties by recency, so start from that (stable sorts).
+    sort_by_list_time(sessions, list_time_ms_map);
+    match sort {
+        SessionSort::Recent => {}
+        SessionSort::Oldest => sessions.reverse(),
+        SessionSort::Project => sessions.sort_by_cached_key(|s| s.project.to_lowercase()),
+        SessionSort::Model => sessions.sort_by_cached_key(|s| s.model.to_lowercase()),
+        SessionSort::Resumed => sessions.sort_by_cached_key(|s| {
+            Reverse(resumed.get(&s.source.internal_key(&s.session_id)).copied())
+        }),
+    }
+}
+
+fn sort_by_list_time(sessions: &mut [SessionInfo], list_time_ms_map: &HashMap<String, i64>) {
+    sessions.sort_by_cached_key(|s| {
+        Reverse(
+            *list_time_ms_map
+                .get(&s.source.internal_key(&s.session_id))
+                .unwrap_or(&s.timestamp),
+        )
+    });
+}
+
+fn selected_session_key(filtered: &[SessionInfo], list_state: &ListState) -> Option<String> {
+    filtered
+        .get(list_state.selected().unwrap_or_default())
+        .map(|s| s.source.internal_key(&s.session_id))
+}
+
+fn reveal_status(result: Result<PathBuf>) -> String {
+    match result {
+        Ok(path) => format!("revealed {}", path.display()),
+        Err(err) => format!("reveal failed: {err}"),
+    }
+}
+
+fn select_session_by_key(filtered: &[SessionInfo], list_state: &mut ListState, key: Option<&str>) {
+    let idx = key
+        .and_then(|key| {
+            filtered
+                .iter()
+                .position(|s| s.source.internal_key(&s.session_id) == key)
+        })
+        .unwrap_or(0);
+    list_state.select(Some(idx));
+}
+
+fn state_dir() -> PathBuf {
+    data_home().join(".local").join("state").join("cs-rs")
+}
+
+/// A small JSON file in `state_dir()`. A missing or unreadable file loads
+/// as the default, and failed saves are ignored: losing it costs only
+/// convenience.
+trait StateFile: Default + Serialize + for<'de> Deserialize<'de> {
+    const FILE_NAME: &'static str;
+
+    fn load() -> Self {
+        fs::read_to_string(state_dir().join(Self::FILE_NAME))
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let path = state_dir().join(Self::FILE_NAME);
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(raw) = serde_json::to_string(self) {
+            let _ = fs::write(path, raw);
+        }
+    }
+}
+
+/// Where the TUI was when it last exited, restored on the next launch.
+#[derive(Default, Deserialize, Serialize)]
+struct TuiState {
+    selected_key: Option<String>,
+    in_detail: bool,
+}
+
+impl StateFile for TuiState {
+    const FILE_NAME: &'static str = "tui-state.json";
+}
+
+/// The rows of the last `cs list`, in order, so `cs view 3` opens the third.
+#[derive(Default, Deserialize, Serialize)]
+struct LastList {
+    sessions: Vec<(SessionSource, String)>,
+}
+
+impl StateFile for LastList {
+    const FILE_NAME: &'static str = "last-list.json";
+}
+
+/// A 1-based row number from `cs list` rather than an ID prefix. Prefixes
+/// need at least 4 characters, so up to three digits is never ambiguous.
+fn list_row_arg(arg: &str) -> Option<usize> {
+    if arg.is_empty() || arg.len() > 3 || !arg.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    arg.parse().ok().filter(|row| *row > 0)
+}
+
+/// Resolves an `archive`/`delete` target, refusing `cs list` row numbers.
+fn resolve_destructive_arg(
+    store: &mut SessionStore,
+    arg: &str,
+    command: &str,
+) -> Result<SessionInfo> {
+    if list_row_arg(arg.trim()).is_some() {
+        return Err(anyhow!(
+            "`cs {command}` needs a session ID, not a list row number ({arg})"
+        ));
+    }
+    store.resolve_session_id(arg)
+}
+
+fn run_tui(watch: bool, archived: bool) -> Result<()> {
+    let mut store = SessionStore::new();
+    let mut sessions = store.all();
+    if !archived {
+        sessions.retain(|s| !s.is_archived());
+    }
+    let mut list_time_ms_map = build_list_time_ms_map(&sessions);
+    let mut list_time_by_session = build_list_time_str_map(&list_time_ms_map);
+    // Stat'ed once here and on watch ticks, not on every frame.
+    let mut file_size_map = build_file_size_map(&sessions);
+    sort_by_list_time(&mut sessions, &list_time_ms_map);
+    store.pinned_first(&mut sessions);
+    let mut filtered = sessions.clone();
+    let mut list_state = ListState::default();
+    list_state.select(Some(0));
+
+    let mut filter = String::new();
+    let mut previous_filter = String::new();
+    let mut filter_input = false;
+    let mut tag_input: Option<String> = None;
+    let mut tagged_only = false;
+    let mut in_detail = false;
+    let mut detail = DetailLines::default();
+    let mut detail_scroll: usize = 0;
+    let mut detail_match: Option<usize> = None;
+    let mut status_message: Option<String> = None;
+    // Thinking and per-turn timestamps, toggled with `T` / `s`.
+    // Escape codes would corrupt the terminal UI, so they are always stripped.
+    let mut detail_options = RenderOptions {
+        strip_ansi: true,
+        ..RenderOptions::default()
+    };
+    // Set when a detail view was requested, so one frame with the "Reading
+    // session…" status is drawn before the (possibly slow) file read.
+    let mut pending_detail_open = false;
+    // Deadline for re-running a typed filter; see `FILTER_DEBOUNCE`.
+    let mut filter_pending: Option<Instant> = None;
+    // The typed filter being matched incrementally; see `FilterScan`.
+    let mut filter_scan: Option<FilterScan> = None;
+    let mut sort = SessionSort::default();
+    let mut show_help = false;
+
+    let saved_state = TuiState::load();
+    select_session_by_key(
+        &filtered,
+        &mut list_state,
+        saved_state.selected_key.as_deref(),
+    );
+    if saved_state.in_detail
+        && selected_session_key(&filtered, &list_state) == saved_state.selected_key
+    {
+        open_selected_detail(
+            &mut store,
+            &filtered,
+            &list_state,
+            &mut detail,
+            &mut in_detail,
+            &mut detail_scroll,
+            detail_options,
+        );
+    }
+
+    let mut terminal = init_terminal()?;
+
+    loop {
+        terminal.draw(|f| {
+            let size = f.size();
+            let top_height = if in_detail {
+                0u16
+            } else {
+                ((filter_input || tag_input.is_some()) as u16) + 1
+            };
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(top_height),
+                    Constraint::Min(1),
+                    Constraint::Length(1),
+                ])
+                .split(size);
+
+            let status = if in_detail {
+                let match_count = detail_match_lines(&detail.lines, &filter).len();
+                let match_status = match (detail_match, match_count) {
+                    (_, 0) => String::new(),
+                    (Some(idx), count) => format!("  [n/N] match {}/{count}", idx + 1),
+                    (None, count) => format!("  [n/N] {count} matches"),
+                };
+                let total = detail.lines.len();
+                let visible = chunks[1].height.saturating_sub(2) as usize;
+                let last_visible = total.min(detail_scroll + visible);
+                let position = if total == 0 {
+                    String::new()
+                } else {
+                    format!(
+                        "  line {}-{last_visible} of {total} ({}%)",
+                        (detail_scroll + 1).min(total),
+                        last_visible * 100 / total
+                    )
+                };
+                let message = status_message
+                    .as_deref()
+                    .map(|m| format!("  {m}"))
+                    .unwrap_or_default();
+                let thinking_hint = if detail_options.thinking { "[T] hide thinking" } else { "[T] thinking" };
+                let time_hint = if detail_options.timestamps { "[s] hide times" } else { "[s] times" };
+                format!(" [↑/↓] scroll  [c] copy  {thinking_hint}  {time_hint}  [Esc]/[b] back  [?] help  [Ctrl-c]/[q] quit{match_status}{position}{message}")
+            } else {
+                let tag_status = if tagged_only { "  [b] all sessions" } else { "  [b] tagged only" };
+                let large_count = filtered
+                    .iter()
+                    .filter(|s| {
+                        let key = s.source.internal_key(&s.session_id);
+                        file_size_label(file_size_map.get(&key).copied()).1
+                    })
+                    .count();
+                let large_status = if large_count > 0 {
+                    format!(
+                        "  ⚠ {large_count} large (>{})",
+                        human_file_size(config().large_file_threshold_bytes())
+                    )
+                } else {
+                    String::new()
+                };
+                let filter_status = if filter_pending.is_some() {
+                    "  searching…".to_string()
+                } else if let Some(scan) = &filter_scan {
+                    format!(
+                        "  searching… {} match(es) so far ({}/{})",
+                        filtered.len(),
+                        scan.next,
+                        scan.pool.len()
+                    )
+                } else if filter.is_empty() {
+                    String::new()
+                } else {
+                    format!("  {} match(es)", filtered.len())
+                };
+                let message = status_message
+                    .as_deref()
+                    .map(|m| format!("  {m}"))
+                    .unwrap_or_default();
+                let sort_status = format!("  [s] sort: {}", sort.label());
+                format!(" [↑/↓ or Ctrl-u/Ctrl-d] navigate  [Enter] resume  [Option-v] view  [/] search  [t] tag  [P] pin{tag_status}{sort_status}  [?] help  [Ctrl-c]/[q] quit{filter_status}{large_status}{message}")
+            };
+
+            if !in_detail {
+                let filter_text = if let Some(tag) = tag_input.as_deref() {
+                    format!("Tag (toggle, Enter to save): {tag}")
+                } else if filter_input {
+                    format!("> {filter}")
+                } else {
+                    "Type to full-text search sessions...".to_string()
+                };
+                f.render_widget(
+                    Paragraph::new(filter_text)
+                        .style(Style::default().fg(Color::White))
+                        .block(Block::default().borders(Borders::BOTTOM)),
+                    chunks[0],
+                );
+            }
+
+            if in_detail {
+                let line_count = detail.lines.len();
+                let visible = chunks[1].height as usize;
+                let end = detail.lines.len().min(detail_scroll + visible);
+                let lines = if detail_scroll < line_count {
+                    styled_detail_lines(&detail, detail_scroll, end)
+                } else {
+                    Vec::new()
+                };
+                f.render_widget(
+                    Paragraph::new(lines)
+                        .block(Block::default().borders(Borders::ALL).title("Session")),
+                    chunks[1],
+                );
+            } else if sessions.is_empty() {
+                f.render_widget(
+                    Paragraph::new(no_sessions_message())
+                        .style(Style::default().fg(Color::DarkGray))
+                        .block(Block::default().borders(Borders::ALL).title("Sessions")),
+                    chunks[1],
+                );
+            } else {
+                let source_w = SessionSource::list_label_width();
+                let items: Vec<ListItem> = filtered
+                    .iter()
+                    .map(|s| {
+                        let key = s.source.internal_key(&s.session_id);
+                        let time = list_time_by_session.get(&key).cloned().unwrap_or_default();
+                        let time_style = session_age(
+                            list_time_ms_map.get(&key).copied().unwrap_or(s.timestamp),
+                        )
+                        .style();
+                        let id_tail = s.list_id_tail();
+                        let wt = if is_worktree(&s.project) { "[wt]" } else { "" };
+                        let project = truncate(&short_project(&s.project), 38);
+                        let (size, is_large_size) =
+                            file_size_label(file_size_map.get(&key).copied());
+                        let prompt_w = (chunks[1].width as usize)
+                            .saturating_sub(7 + 3 + source_w + 3 + 5 + 3 + 38 + 1 + 4 + 3 + 8 + 3)
+                            .max(20);
+                        let pin = if store.is_pinned(s) { "▲ " } else { "" };
+                        let archived = if s.is_archived() { "(archived) " } else { "" };
+                        let tags = store
+                            .session_tags(s)
+                            .iter()
+                            .map(|tag| format!("#{tag} "))
+                            .collect::<String>();
+                        let prompt = truncate(
+                            &s.display,
+                            prompt_w.saturating_sub(
+                                pin.width() + archived.width() + tags.width(),
+                            ),
+                        );
+                        let source = s.source.list_label();
+                        let source_style = if s.source == SessionSource::Codex {
+                            Style::default().fg(Color::Rgb(88, 166, 255))
+                        } else {
+                            // Anthropic-style orange for cc rows.
+                            Style::default().fg(Color::Rgb(217, 119, 87))
+                        };
+                        let size_style = if is_large_size {
+                            Style::default().fg(Color::Red)
+                        } else {
+                            Style::default().fg(Color::DarkGray)
+                        };
+                        let row = Line::from(vec![
+                            Span::styled(format!("{time:>7}"), time_style),
+                            Span::from("   "),
+                            Span::styled(fit_width(source, source_w), source_style),
+                            Span::from("   "),
+                            Span::styled(format!("{id_tail:>5}"), Style::default().fg(Color::DarkGray)),
+                            Span::from("   "),
+                            Span::styled(format!("{project:38}"), Style::default().fg(Color::Cyan)),
+                            Span::from(" "),
+                            Span::styled(format!("{wt:4}"), Style::default().fg(Color::Yellow)),
+                            Span::from("   "),
+                            Span::styled(format!("{size:>8}"), size_style),
+                            Span::from("   "),
+                            Span::styled(pin, Style::default().fg(Color::Yellow)),
+                            Span::styled(archived, Style::default().fg(Color::DarkGray)),
+                            Span::styled(tags, Style::default().fg(Color::Magenta)),
+                            Span::from(prompt),
+                        ]);
+                        ListItem::new(row)
+                    })
+                    .collect();
+
+                let list = List::new(items)
+                    .block(Block::default().borders(Borders::ALL).title("Sessions"))
+                    .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+                    .highlight_symbol("> ");
+                f.render_stateful_widget(list, chunks[1], &mut list_state);
+            }
+
+            f.render_widget(
+                Paragraph::new(status).style(Style::default().fg(Color::White)),
+                chunks[2],
+            );
+
+            if show_help {
+                let mode = if in_detail {
+                    TuiMode::Detail
+                } else if filter_input {
+                    TuiMode::Filter
+                } else {
+                    TuiMode::List
+                };
+                render_help_overlay(f, mode);
+            }
+        })?;
+
+        if pending_detail_open {
+            pending_detail_open = false;
+            open_selected_detail(
+                &mut store,
+                &filtered,
+                &list_state,
+                &mut detail,
+                &mut in_detail,
+                &mut detail_scroll,
+                detail_options,
+            );
+            land_on_first_match(
+                &detail.lines,
+                &filter,
+                &mut detail_scroll,
+                &mut detail_match,
+            );
+            status_message = None;
+            continue;
+        }
+
+        if let Some(deadline) = filter_pending {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(wait)? {
+                filter_pending = None;
+                let selected_key = selected_session_key(&filtered, &list_state);
+                filter_scan = Some(FilterScan::start(
+                    &mut filtered,
+                    &sessions,
+                    &mut previous_filter,
+                    &filter,
+                    tagged_only,
+                    selected_key,
+                ));
+                list_state.select(Some(0));
+                continue;
+            }
+        }
+
+        if let Some(scan) = filter_scan.as_mut() {
+            // Keep matching between keystrokes; a key cancels or narrows the
+            // scan through the handlers below.
+            if !event::poll(StdDuration::ZERO)? {
+                let done = scan.step(&mut store, &mut filtered, FILTER_SCAN_BUDGET);
+                if let Some(key) = scan.restore_key.as_deref() {
+                    let moved = list_state.selected().unwrap_or_default() != 0;
+                    let found = filtered
+                        .iter()
+                        .position(|s| s.source.internal_key(&s.session_id) == key);
+                    if let Some(idx) = found.filter(|_| !moved) {
+                        list_state.select(Some(idx));
+                    }
+                    if moved || found.is_some() {
+                        scan.restore_key = None;
+                    }
+                }
+                if done {
+                    previous_filter.clone_from(&filter);
+                    filter_scan = None;
+                }
+                continue;
+            }
+        }
+
+        let poll_interval = if watch {
+            StdDuration::from_secs(2)
+        } else {
+            StdDuration::from_secs(60)
+        };
+        if !event::poll(poll_interval)? {
+            if watch {
+                // Re-check file mtimes so sessions being written elsewhere move to
+                // the top and the open detail view picks up new turns.
+                let refreshed = build_list_time_ms_map(&sessions);
+                let selected_key = selected_session_key(&filtered, &list_state);
+                if refreshed != list_time_ms_map {
+                    if in_detail {
+                        if let Some(key) = selected_key.as_deref() {
+                            if refreshed.get(key) != list_time_ms_map.get(key) {
+                                let scroll = detail_scroll;
+                                open_selected_detail(
+                                    &mut store,
+                                    &filtered,
+                                    &list_state,
+                                    &mut detail,
+                                    &mut in_detail,
+                                    &mut detail_scroll,
+                                    detail_options,
+                                );
+                                detail_scroll = scroll.min(detail.lines.len().saturating_sub(1));
+                            }
+                        }
+                    }
+                    list_time_ms_map = refreshed;
+                    file_size_map = build_file_size_map(&sessions);
+                    sort_sessions(&mut sessions, sort, &list_time_ms_map, &store.cache.resumed);
+                    sort_sessions(&mut filtered, sort, &list_time_ms_map, &store.cache.resumed);
+                    store.pinned_first(&mut sessions);
+                    store.pinned_first(&mut filtered);
+                    select_session_by_key(&filtered, &mut list_state, selected_key.as_deref());
+                }
+                list_time_by_session = build_list_time_str_map(&list_time_ms_map);
+            }
+            continue;
+        }
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            break;
+        }
+
+        status_message = None;
+
+        if show_help {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Char('?') | KeyCode::F(1)) {
+                show_help = false;
+            }
+            continue;
+        }
+        // `?` stays typeable in a search query; F1 opens help there instead.
+        let help_key =
+            key.code == KeyCode::F(1) || (key.code == KeyCode::Char('?') && !filter_input);
+        if help_key && tag_input.is_none() {
+            show_help = true;
+            continue;
+        }
+
+        if in_detail {
+            match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('T') | KeyCode::Char('s') => {
+                    if key.code == KeyCode::Char('T') {
+                        detail_options.thinking = !detail_options.thinking;
+                    } else {
+                        detail_options.timestamps = !detail_options.timestamps;
+                    }
+                    let turn = detail_turn_at(&detail.lines, detail_scroll);
+                    open_selected_detail(
+                        &mut store,
+                        &filtered,
+                        &list_state,
+                        &mut detail,
+                        &mut in_detail,
+                        &mut detail_scroll,
+                        detail_options,
+                    );
+                    detail_scroll = detail_turn_start(&detail.lines, turn);
+                    detail_match = None;
+                }
+                KeyCode::Char('c') => {
+                    status_message = Some(match copy_to_clipboard(&detail.lines.join("\n")) {
+                        Ok(()) => format!("copied {} lines", detail.lines.len()),
+                        Err(err) => format!("copy failed: {err}"),
+                    });
+                }
+                KeyCode::Char('o') => {
+                    let idx = list_state.selected().unwrap_or_default();
+                    if let Some(selected) = filtered.get(idx) {
+                        status_message = Some(reveal_status(reveal_session_file(selected)));
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('b') | KeyCode::Left => {
+                    in_detail = false;
+                    detail_scroll = 0;
+                    detail_match = None;
+                    detail = DetailLines::default();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    let matches = detail_match_lines(&detail.lines, &filter);
+                    let forward = key.code == KeyCode::Char('n');
+                    jump_to_detail_match(&matches, &mut detail_scroll, &mut detail_match, forward);
+                }
+                KeyCode::Up => {
+                    detail_scroll = detail_scroll.saturating_sub(1);
+                }
+                KeyCode::Down if detail_scroll + 1 < detail.lines.len() => {
+                    detail_scroll += 1;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(tag) = tag_input.as_mut() {
+            match key.code {
+                KeyCode::Esc => tag_input = None,
+                KeyCode::Backspace => {
+                    tag.pop();
+                }
+                KeyCode::Enter => {
+                    let idx = list_state.selected().unwrap_or_default();
+                    if let Some(selected) = filtered.get(idx).cloned() {
+                        store.toggle_session_tag(&selected, tag);
+                    }
+                    tag_input = None;
+                    if tagged_only {
+                        filter_scan = None;
+                        previous_filter.clear();
+                        refresh_filter_results(
+                            &mut store,
+                            &mut filtered,
+                            &sessions,
+                            &mut previous_filter,
+                            &filter,
+                            tagged_only,
+                        );
+                        let len = filtered.len();
+                        list_state.select(Some(idx.min(len.saturating_sub(1))));
+                    }
+                }
+                KeyCode::Char(c) if !c.is_control() && !c.is_whitespace() => {
+                    tag.push(c);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if filter_input {
+            if filter_pending.is_some() && (is_view_shortcut(&key) || key.code == KeyCode::Enter) {
+                // Act on the typed query, not the results of an older one.
+                filter_pending = None;
+                filter_scan = None;
+                let selected_key = selected_session_key(&filtered, &list_state);
+                refresh_filter_results(
+                    &mut store,
+                    &mut filtered,
+                    &sessions,
+                    &mut previous_filter,
+                    &filter,
+                    tagged_only,
+                );
+                select_session_by_key(&filtered, &mut list_state, selected_key.as_deref());
+            }
+            if is_view_shortcut(&key) {
+                pending_detail_open = true;
+                status_message = Some("Reading session…".to_string());
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Esc => {
+                    let selected_key = selected_session_key(&filtered, &list_state);
+                    filter_input = false;
+                    filter_pending = None;
+                    filter_scan = None;
+                    filter.clear();
+                    refresh_filter_results(
+                        &mut store,
+                        &mut filtered,
+                        &sessions,
+                        &mut previous_filter,
+                        &filter,
+                        tagged_only,
+                    );
+                    select_session_by_key(&filtered, &mut list_state, selected_key.as_deref());
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    filter_scan = None;
+                    filter_pending = Some(Instant::now() + FILTER_DEBOUNCE);
+                }
+                KeyCode::Up => {
+                    let prev = match list_state.selected() {
+                        Some(0) | None => 0,
+                        Some(i) => i.saturating_sub(1),
+                    };
+                    list_state.select(Some(prev));
+                }
+                KeyCode::Down => {
+                    let len = filtered.len();
+                    let next = match list_state.selected() {
+                        Some(i) => {
+                            if i + 1 < len {
+                                i + 1
+                            } else {
+                                i
+                            }
+                        }
+                        None => 0,
+                    };
+                    list_state.select(Some(next));
+                }
+                KeyCode::Enter => {
+                    let idx = list_state.selected().unwrap_or_default();
+                    if idx < filtered.len() {
+                        let selected = &filtered[idx];
+                        let session = store
+                            .get_exact(selected.source, &selected.session_id)
+                            .unwrap_or_else(|| selected.clone());
+                        TuiState {
+                            selected_key: selected_session_key(&filtered, &list_state),
+                            in_detail: false,
+                        }
+                        .save();
+                        cleanup_terminal(&mut terminal)?;
+                        resume_session(&mut store, &session, &ResumeOptions::default())?;
+                        return Ok(());
+                    }
+                }
+                KeyCode::Char(c) if !c.is_control() && key.modifiers.is_empty() => {
+                    filter.push(c);
+                    filter_scan = None;
+                    filter_pending = Some(Instant::now() + FILTER_DEBOUNCE);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('u') => {
+                    let prev = match list_state.selected() {
+                        Some(0) | None => 0,
+                        Some(i) => i.saturating_sub(1),
+                    };
+                    list_state.select(Some(prev));
+                    continue;
+                }
+                KeyCode::Char('d') => {
+                    let len = filtered.len();
+                    let next = match list_state.selected() {
+                        Some(i) => {
+                            if i + 1 < len {
+                                i + 1
+                            } else {
+                                i
+                            }
+                        }
+                        None => 0,
+                    };
+                    list_state.select(Some(next));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if key.code == KeyCode::Right || is_view_shortcut(&key) {
+            pending_detail_open = true;
+            status_message = Some("Reading session…".to_string());
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => break,
+            KeyCode::Char('/') => {
+                filter_input = true;
+                filter_scan = None;
+                filter.clear();
+                refresh_filter_results(
+                    &mut store,
+                    &mut filtered,
+                    &sessions,
+                    &mut previous_filter,
+                    &filter,
+                    tagged_only,
+                );
+            }
+            KeyCode::Char('t') if list_state.selected().unwrap_or_default() < filtered.len() => {
+                tag_input = Some(String::new());
+            }
+            KeyCode::Char('o') => {
+                let idx = list_state.selected().unwrap_or_default();
+                if let Some(selected) = filtered.get(idx) {
+                    status_message = Some(reveal_status(reveal_session_file(selected)));
+                }
+            }
+            KeyCode::Char('s') => {
+                let selected_key = selected_session_key(&filtered, &list_state);
+                sort = sort.next();
+                sort_sessions(&mut sessions, sort, &list_time_ms_map, &store.cache.resumed);
+                sort_sessions(&mut filtered, sort, &list_time_ms_map, &store.cache.resumed);
+                store.pinned_first(&mut sessions);
+                store.pinned_first(&mut filtered);
+                select_session_by_key(&filtered, &mut list_state, selected_key.as_deref());
+                // Matches still to come would arrive in the old order.
+                if filter_scan.is_some() {
+                    filter_scan = Some(FilterScan::start(
+                        &mut filtered,
+                        &sessions,
+                        &mut previous_filter,
+                        &filter,
+                        tagged_only,
+                        selected_key,
+                    ));
+                    list_state.select(Some(0));
+                }
+            }
+            KeyCode::Char('P') => {
+                let selected_key = selected_session_key(&filtered, &list_state);
+                let idx = list_state.selected().unwrap_or_default();
+                if let Some(selected) = filtered.get(idx).cloned() {
+                    store.toggle_pinned(&selected);
+                    sort_sessions(&mut sessions, sort, &list_time_ms_map, &store.cache.resumed);
+                    sort_sessions(&mut filtered, sort, &list_time_ms_map, &store.cache.resumed);
+                    store.pinned_first(&mut sessions);
+                    store.pinned_first(&mut filtered);
+                    select_session_by_key(&filtered, &mut list_state, selected_key.as_deref());
+                    if filter_scan.is_some() {
+                        filter_scan = Some(FilterScan::start(
+                            &mut filtered,
+                            &sessions,
+                            &mut previous_filter,
+                            &filter,
+                            tagged_only,
+                            selected_key,
+                        ));
+                        list_state.select(Some(0));
+                    }
+                }
+            }
+            KeyCode::Char('b') => {
+                tagged_only = !tagged_only;
+                filter_scan = None;
+                previous_filter.clear();
+                refresh_filter_results(
+                    &mut store,
+                    &mut filtered,
+                    &sessions,
+                    &mut previous_filter,
+                    &filter,
+                    tagged_only,
+                );
+                list_state.select(Some(0));
+            }
+            KeyCode::Esc => break,
+            KeyCode::Up => {
+                let prev = match list_state.selected() {
+                    Some(0) | None => 0,
+                    Some(i) => i.saturating_sub(1),
+                };
+                list_state.select(Some(prev));
+            }
+            KeyCode::Down => {
+                let len = filtered.len();
+                let next = match list_state.selected() {
+                    Some(i) => {
+                        if i + 1 < len {
+                            i + 1
+                        } else {
+                            i
+                        }
+                    }
+                    None => 0,
+                };
+                list_state.select(Some(next));
+            }
+            KeyCode::Enter => {
+                let idx = list_state.selected().unwrap_or_default();
+                if idx < filtered.len() {
+                    let selected = &filtered[idx];
+                    let session = store
+                        .get_exact(selected.source, &selected.session_id)
+                        .unwrap_or_else(|| selected.clone());
+                    TuiState {
+                        selected_key: selected_session_key(&filtered, &list_state),
+                        in_detail: false,
+                    }
+                    .save();
+                    cleanup_terminal(&mut terminal)?;
+                    resume_session(&mut store, &session, &ResumeOptions::default())?;
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    TuiState {
+        selected_key: selected_session_key(&filtered, &list_state),
+        in_detail,
+    }
+    .save();
+    cleanup_terminal(&mut terminal)?;
+    Ok(())
+}
+
+fn apply_filter(
+    store: &mut SessionStore,
+    filtered: &mut Vec<SessionInfo>,
+    sessions: &[SessionInfo],
+    filter: &str,
+) {
+    let q = filter.to_lowercase();
+    if q.is_empty() {
+        filtered.clear();
+        filtered.extend_from_slice(sessions);
+        return;
+    }
+
+    filtered.clear();
+    for session in sessions {
+        if session_matches_filter(store, session, &q) {
+            filtered.push(session.clone());
+        }
+    }
+}
+
+/// Whether `session` matches a lowercased TUI filter by title, project, ID,
+/// source, or (reading the file if needed) message text.
+fn session_matches_filter(store: &mut SessionStore, session: &SessionInfo, q: &str) -> bool {
+    session.display.to_lowercase().contains(q)
+        || session.project.to_lowercase().contains(q)
+        || session.session_id.to_lowercase().contains(q)
+        || session.source.label().to_lowercase().contains(q)
+        || session
+            .source
+            .default_list_label()
+            .to_lowercase()
+            .contains(q)
+        || store.session_contains_full_text(session, q)
+}
+
+fn init_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>> {
+    static PANIC_HOOK: std::sync::Once = std::sync::Once::new();
+    PANIC_HOOK.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+    });
+
+    let mut out = stdout();
+    out.execute(EnterAlternateScreen)?;
+    enable_raw_mode()?;
+
+    let backend = CrosstermBackend::new(out);
+    let terminal = Terminal::new(backend)?;
+    Ok(terminal)
+}
+
+fn cleanup_terminal<B: std::io::Write>(terminal: &mut Terminal<CrosstermBackend<B>>) -> Result<()> {
+    disable_raw_mode()?;
+    terminal.backend_mut().execute(LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Best-effort terminal reset for paths that never reach `cleanup_terminal`
+/// (panics and errors bubbling out of the TUI loop).
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let mut out = stdout();
+    let _ = out.execute(LeaveAlternateScreen);
+    let _ = out.execute(crossterm::cursor::Show);
+}
+
+/// Quotes `value` as one literal POSIX shell word: wrapped in single quotes,
+/// with each `'` written as `'\''`, so `$`, backticks, and `\` stay inert.
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[derive(Default)]
+struct ResumeOptions {
+    /// Resume here instead of the session's recorded project path.
+    cwd: Option<PathBuf>,
+    /// Recreate a missing project directory instead of refusing.
+    create_cwd: bool,
+    /// Print the assembled command instead of running it (also `CS_RS_DRY_RUN=1`).
+    dry_run: bool,
+    /// Use the newest model (and its effort) seen for the session's source
+    /// instead of the one it recorded (also `resume_with_latest_model`).
+    latest_model: bool,
+}
+
+/// Where a resume would run, worked out without touching the filesystem or
+/// the terminal so `--dry-run` can report it.
+#[derive(Debug, PartialEq, Eq)]
+enum ResumeCwd {
+    Ready(PathBuf),
+    /// Missing, and `--create-cwd` asked for it to be recreated.
+    Create(PathBuf),
+    /// Unknown or missing; the user has to pick a directory.
+    Prompt {
+        problem: String,
+        hint: String,
+    },
+}
+
+fn plan_resume_cwd(session: &SessionInfo, options: &ResumeOptions) -> Result<ResumeCwd> {
+    if let Some(dir) = options.cwd.as_deref() {
+        if !dir.is_dir() {
+            return Err(anyhow!("--cwd is not a directory: {}", dir.display()));
+        }
+        return Ok(ResumeCwd::Ready(dir.to_path_buf()));
+    }
+
+    if session.project.is_empty() {
+        return Err(anyhow!("Session project path is empty"));
+    }
+    // A relative project can't be located: joining it to whatever directory
+    // cs-rs runs from would resume (or create a directory) in the wrong place.
+    let Some(configured) = absolute_project_path(&session.project) else {
+        return Ok(ResumeCwd::Prompt {
+            problem: format!(
+                "Session project path {} is relative, so its directory is unknown.",
+                session.project
+            ),
+            hint: "pass --cwd to choose the directory to resume in".to_string(),
+        });
+    };
+
+    if configured.exists() {
+        return Ok(ResumeCwd::Ready(configured));
+    }
+
+    if !options.create_cwd {
+        return Ok(ResumeCwd::Prompt {
+            problem: format!(
+                "Project directory {} no longer exists.",
+                configured.display()
+            ),
+            hint: format!(
+                "pass --cwd to resume elsewhere or --create-cwd to recreate {}",
+                configured.display()
+            ),
+        });
+    }
+    Ok(ResumeCwd::Create(configured))
+}
+
+fn resolve_resume_cwd(session: &SessionInfo, options: &ResumeOptions) -> Result<PathBuf> {
+    match plan_resume_cwd(session, options)? {
+        ResumeCwd::Ready(dir) => Ok(dir),
+        ResumeCwd::Create(dir) => {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create project directory {}", dir.display()))?;
+            Ok(dir)
+        }
+        ResumeCwd::Prompt { problem, hint } => prompt_for_resume_dir(&problem, &hint),
+    }
+}
+
+/// The project as an absolute path, expanding a leading `~`. `None` for
+/// relative paths, which have no reliable base directory.
+fn absolute_project_path(project: &str) -> Option<PathBuf> {
+    if project == "~" {
+        return Some(home_dir());
+    }
+    if let Some(rest) = project.strip_prefix("~/") {
+        return Some(home_dir().join(rest));
+    }
+    let path = Path::new(project);
+    path.is_absolute().then(|| path.to_path_buf())
+}
+
+/// Asks on the terminal for a directory to resume in, after explaining the
+/// `problem`. Without a terminal, fails with the problem and `hint`.
+fn prompt_for_resume_dir(problem: &str, hint: &str) -> Result<PathBuf> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!("{problem} ({hint})"));
+    }
+
+    eprint!("{problem}\nEnter a directory to resume in (blank to cancel): ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Err(anyhow!("Resume cancelled; {hint}"));
+    }
+    let dir = PathBuf::from(answer);
+    if !dir.is_dir() {
+        return Err(anyhow!("Not a directory: {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+fn resume_session(
+    store: &mut SessionStore,
+    session: &SessionInfo,
+    options: &ResumeOptions,
+) -> Result<()> {
+    let latest_model = options.latest_model || config().resume_with_latest_model;
+    let latest = if latest_model {
+        store.most_recent_model_for_source(session.source, None)
+    } else {
+        None
+    };
+    let session = &match latest {
+        Some((model, effort)) => SessionInfo {
+            model,
+            reasoning_effort: if effort.is_empty() {
+                session.reasoning_effort.clone()
+            } else {
+                effort
+            },
+            ..session.clone()
+        },
+        None => session.clone(),
+    };
+    let session_id = shell_single_quote(&session.session_id);
+    let resume_cmd = session.source.resume_command();
+    let fallback = session.source.fallback_resume_command();
+    let resume_invocation = session.source.resume_invocation();
+    // Claude Code: skip --model so the user's account default (including context
+    // window tier) takes effect, unless the latest model was asked for. Codex:
+    // pass --model to preserve the exact model.
+    let model_arg = if session.source == SessionSource::Claudecode && latest_model {
+        if session.model.trim().is_empty() {
+            String::new()
+        } else {
+            format!(
+                " {} {}",
+                session.source.resume_model_flag(),
+                shell_single_quote(&session.model)
+            )
+        }
+    } else if session.source == SessionSource::Codex {
+        if let Some(model) = codex_model_candidate(&session.model) {
+            format!(
+                " {} {}",
+                session.source.resume_model_flag(),
+                shell_single_quote(&model)
+            )
+        } else {
+            String::new()
+        }
+    } else {
+        String::new()
+    };
+    let effort_arg = if session.source == SessionSource::Codex {
+        codex_effort_candidate(&session.reasoning_effort)
+            .map(|effort| {
+                let config_pair = format!("model_reasoning_effort=\"{effort}\"");
+                format!(" -c {}", shell_single_quote(&config_pair))
+            })
+            .unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let extra_args: String = config()
+        .resume_args
+        .get(session.source.cache_key())
+        .into_iter()
+        .flatten()
+        .map(|arg| format!(" {}", shell_single_quote(arg)))
+        .collect();
+    let script = format!(
+        "cs_session_id={session_id}; if whence -w {resume_cmd} >/dev/null 2>&1; then {resume_cmd} {invocation}{model_arg}{effort_arg}{extra_args}; elif whence -w {fallback} >/dev/null 2>&1; then {fallback} {invocation}{model_arg}{effort_arg}{extra_args}; fi",
+        session_id = session_id,
+        resume_cmd = resume_cmd,
+        invocation = resume_invocation,
+        fallback = fallback,
+        model_arg = model_arg,
+        effort_arg = effort_arg,
+        extra_args = extra_args,
+    );
+
+    let shell_flag = config().resume_shell_flag();
+    let dry_run =
+        options.dry_run || env::var("CS_RS_DRY_RUN").is_ok_and(|v| !v.is_empty() && v != "0");
+    if dry_run {
+        // Report the directory without creating it or asking for one.
+        let current_dir = match plan_resume_cwd(session, options)? {
+            ResumeCwd::Ready(dir) => dir.display().to_string(),
+            ResumeCwd::Create(dir) => format!("would create {}", dir.display()),
+            ResumeCwd::Prompt { problem, .. } => {
+                format!("would prompt for a directory ({problem})")
+            }
+        };
+        eprintln!("source:      {}", session.source.label());
+        eprintln!("session:     {}", session.session_id);
+        eprintln!("current_dir: {current_dir}");
+        eprintln!(
+            "model_args: {}",
+            if model_arg.is_empty() {
+                " (none)"
+            } else {
+                &model_arg
+            }
+        );
+        eprintln!(
+            "effort_args:{}",
+            if effort_arg.is_empty() {
+                " (none)"
+            } else {
+                &effort_arg
+            }
+        );
+        eprintln!(
+            "extra_args: {}",
+            if extra_args.is_empty() {
+                " (none)"
+            } else {
+                &extra_args
+            }
+        );
+        eprintln!(
+            "command:     zsh {shell_flag} {}",
+            shell_single_quote(&script)
+        );
+        return Ok(());
+    }
+
+    let project_path = resolve_resume_cwd(session, options)?;
+    store.record_resume(session);
+    let mut cmd = Command::new("zsh");
+    cmd.arg(shell_flag).arg(script);
+    cmd.current_dir(project_path);
+    let status = cmd
+        .status()
+        .with_context(|| "failed to launch shell resume command")?;
+
+    if !status.success() {
+        eprintln!("Resume command exited with status: {status}");
+    }
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+fn home_dir() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_else(|_| String::from("/Users/mbm-gsc")))
+}
+
+/// Parent of `.claude`, `.codex`, and the cs-rs state directory. Defaults to
+/// `$HOME`; `CS_RS_DATA_DIR` swaps in a fixture or sandbox root.
+fn data_home() -> PathBuf {
+    env::var_os("CS_RS_DATA_DIR")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(home_dir)
+}
+
+fn parse_since_ms(since: &str) -> Result<i64> {
+    let since = since.trim();
+    let relative = since
+        .char_indices()
+        .last()
+        .and_then(|(idx, unit)| Some((since[..idx].parse::<i64>().ok()?, unit)));
+    if let Some((amount, unit)) = relative {
+        let delta = match unit {
+            'm' => Some(TimeDelta::try_minutes(amount)),
+            'h' => Some(TimeDelta::try_hours(amount)),
+            'd' => Some(TimeDelta::try_days(amount)),
+            'w' => Some(TimeDelta::try_weeks(amount)),
+            _ => None,
+        };
+        if let Some(delta) = delta.filter(|_| amount >= 0) {
+            return delta
+                .and_then(|delta| Local::now().checked_sub_signed(delta))
+                .map(|ts| ts.timestamp_millis())
+                .with_context(|| format!("--since value is too far in the past: {since}"));
+        }
+    }
+
+    chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map(|date| {
+            date.and_hms_opt(0, 0, 0)
+                .and_then(|naive| display_zone().localize(&naive))
+                .map(|ts| ts.timestamp_millis())
+        })
+        .ok()
+        .flatten()
+        .with_context(|| {
+            format!(
+                "Invalid --since value: {since} (use YYYY-MM-DD or a duration like 30m, 24h, 7d)"
+            )
+        })
+}
+
+/// A `--project` filter as given on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ProjectFilter {
+    /// Matched with `project_matches`.
+    Pattern(String),
+    /// The canonical current directory from `--project .`; only sessions in
+    /// exactly that directory match, not its parents' or siblings'.
+    Exact(PathBuf),
+}
+
+impl ProjectFilter {
+    fn matches(&self, project: &str, fuzzy: bool) -> bool {
+        match self {
+            Self::Pattern(filter) => project_matches(project, filter, fuzzy),
+            Self::Exact(dir) => absolute_project_path(project)
+                .is_some_and(|path| fs::canonicalize(&path).unwrap_or(path) == *dir),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Self::Pattern(filter) => filter.clone(),
+            Self::Exact(dir) => dir.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// `--project .` (or an empty value) means the current working directory.
+fn resolve_project_filter(project: Option<String>) -> Result<Option<ProjectFilter>> {
+    match project.as_deref().map(str::trim) {
+        Some(".") | Some("") => {
+            let cwd = env::current_dir()
+                .and_then(fs::canonicalize)
+                .context("failed to read current directory")?;
+            Ok(Some(ProjectFilter::Exact(cwd)))
+        }
+        _ => Ok(project.map(ProjectFilter::Pattern)),
+    }
+}
+
+#[derive(Default)]
+struct ListFilters {
+    project: Option<ProjectFilter>,
+    since: Option<String>,
+    tag: Option<String>,
+    model: Option<String>,
+    /// Only sessions that started at or after this session.
+    since_session: Option<String>,
+    /// Hide sessions with fewer user/assistant messages (reads session files).
+    min_messages: Option<usize>,
+    /// Also match `project` as a subsequence of the project name.
+    project_fuzzy: bool,
+    source: Option<SessionSource>,
+    /// Include sessions moved to `archived_sessions`.
+    archived: bool,
+}
+
+impl ListFilters {
+    /// `project=foo since=7d ...` for the active filters, or `None` when the
+    /// list is unfiltered.
+    fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                if value.contains(char::is_whitespace) {
+                    parts.push(format!("{key}={value:?}"));
+                } else {
+                    parts.push(format!("{key}={value}"));
+                }
+            }
+        };
+        push("source", self.source.map(|s| s.list_label().to_string()));
+        push("project", self.project.as_ref().map(ProjectFilter::label));
+        push("since", self.since.clone());
+        push("since-session", self.since_session.clone());
+        push("tag", self.tag.clone());
+        push("model", self.model.clone());
+        push("min-messages", self.min_messages.map(|n| n.to_string()));
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+/// Case-insensitive `--project` match: a substring of the path, or with
+/// `fuzzy`, the filter's characters in order within the project's directory
+/// name (`acbk` matches `~/w/acme-backend`).
+fn project_matches(project: &str, filter: &str, fuzzy: bool) -> bool {
+    let project = project.to_lowercase();
+    let filter = filter.to_lowercase();
+    if project.contains(&filter) {
+        return true;
+    }
+    if !fuzzy || filter.is_empty() {
+        return false;
+    }
+    let name = project
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default();
+    let mut name_chars = name.chars();
+    filter.chars().all(|c| name_chars.any(|n| n == c))
+}
+
+fn filter_sessions(store: &mut SessionStore, filters: &ListFilters) -> Result<Vec<SessionInfo>> {
+    let mut sessions = store.all();
+
+    if !filters.archived {
+        sessions.retain(|s| !s.is_archived());
+    }
+
+    if let Some(source) = filters.source {
+        sessions.retain(|s| s.source == source);
+    }
+
+    if let Some(tag) = filters.tag.as_deref() {
+        sessions.retain(|s| {
+            store
+                .session_tags(s)
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(tag))
+        });
+    }
+
+    if let Some(p) = &filters.project {
+        let fuzzy = filters.project_fuzzy || config().fuzzy_project;
+        sessions.retain(|s| p.matches(&s.project, fuzzy));
+    }
+
+    if let Some(since_s) = filters.since.as_deref() {
+        let since_ms = parse_since_ms(since_s)?;
+        sessions.retain(|s| s.timestamp >= since_ms);
+    }
+
+    if let Some(id) = filters.since_session.as_deref() {
+        let landmark = store
+            .get(id)
+            .with_context(|| format!("--since-session: session not found: {id}"))?;
+        sessions.retain(|s| s.timestamp >= landmark.timestamp);
+    }
+
+    if let Some(model) = filters.model.as_deref() {
+        let model = model.to_lowercase();
+        sessions = sessions
+            .into_iter()
+            .map(|s| store.with_enriched_model(s))
+            .filter(|s| s.model.to_lowercase().contains(&model))
+            .collect();
+        store.save_cache_if_dirty();
+    }
+
+    if let Some(min) = filters.min_messages {
+        sessions.retain(|s| store.message_count(s) >= min);
+        store.save_cache_if_dirty();
+    }
+
+    sessions.sort_by_cached_key(|s| Reverse(list_time_ms_for_session(s)));
+    Ok(sessions)
+}
+
+fn list_command(
+    store: &mut SessionStore,
+    filters: &ListFilters,
+    limit: usize,
+    format: ListFormat,
+    fields: &[String],
+    sort: SessionSort,
+    table: TableOptions,
+) -> Result<String> {
+    let mut sessions = filter_sessions(store, filters)?;
+    store.sort_for_list(&mut sessions, sort);
+    if sessions.is_empty() && format == ListFormat::Table && store.all().is_empty() {
+        return Ok(no_sessions_message());
+    }
+    // Only the table shows row numbers, so only it may redefine them.
+    if format == ListFormat::Table {
+        LastList {
+            sessions: sessions
+                .iter()
+                .take(unlimited_if_zero(limit))
+                .map(|s| (s.source, s.session_id.clone()))
+                .collect(),
+        }
+        .save();
+    }
+    let header = match filters.describe() {
+        Some(active) if format == ListFormat::Table && !table.compact => {
+            // Out of the sessions the list would show unfiltered, so archived
+            // ones only count with `--archived`.
+            let visible = store
+                .all()
+                .iter()
+                .filter(|s| filters.archived || !s.is_archived())
+                .count();
+            format!(
+                "Filters: {active} ({} of {visible} sessions)\n",
+                sessions.len()
+            )
+        }
+        _ => String::new(),
+    };
+    let mut durations = HashMap::new();
+    if table.show_duration && format == ListFormat::Table {
+        for session in sessions.iter().take(unlimited_if_zero(limit)) {
+            if let Some(ms) = store.session_duration_ms(session) {
+                durations.insert(session.source.internal_key(&session.session_id), ms);
+            }
+        }
+        store.save_cache_if_dirty();
+    }
+    Ok(header + &list_sessions(sessions, format, limit, fields, table, &durations))
+}
+
+/// Explains where sessions were looked for, for fresh installs or a wrong HOME.
+fn no_sessions_message() -> String {
+    let mut out = String::from("No Claude Code or Codex sessions found. Looked in:\n");
+    for source in SessionSource::all() {
+        let base = source.home_base();
+        let note = if base.is_dir() { "" } else { " (missing)" };
+        out.push_str(&format!("  {}{note}\n", base.display()));
+    }
+    out.push_str("Start a session with `claude` or `codex`, or check that HOME is set correctly.");
+    if !ignore_rules().is_empty() {
+        out.push_str(&format!(
+            "\nSome sessions may be hidden by {}.",
+            IgnoreRules::file_path().display()
+        ));
+    }
+    out
+}
+
+#[derive(Parser)]
+#[command(name = "cs-rs", about = "Session tools for Claude Code and Codex")]
+struct Cli {
+    /// Keep the TUI list and open session fresh while session files change
+    #[arg(long)]
+    watch: bool,
+    /// Include sessions moved to archived_sessions in the TUI list
+    #[arg(long)]
+    archived: bool,
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    View {
+        session_id: String,
+        #[arg(long)]
+        thinking: bool,
+        #[arg(short, long)]
+        tail: Option<usize>,
+        /// Show only the first N messages, e.g. to see how a session started
+        #[arg(long, conflicts_with = "tail")]
+        head: Option<usize>,
+        /// Show turns oldest-first instead of the default newest-first order
+        #[arg(long)]
+        reverse: bool,
+        #[arg(long)]
+        no_pager: bool,
+        /// Include internal bookkeeping entries (file-history-snapshot, progress, queue-operation)
+        #[arg(long)]
+        show_internal: bool,
+        /// Emit a versioned JSON document whose messages are {role, model, timestamp, text, tool_calls}
+        #[arg(long)]
+        json: bool,
+        /// Write the rendered conversation to a file instead of the terminal
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Open the rendered conversation in an editor (default $VISUAL, then $EDITOR)
+        #[arg(long, num_args = 0..=1, default_missing_value = "", conflicts_with = "output")]
+        editor: Option<String>,
+        /// Hide tool calls, leaving only natural-language turns
+        #[arg(long, conflicts_with = "tools_only")]
+        no_tools: bool,
+        /// Show only tool calls as a compact action log
+        #[arg(long)]
+        tools_only: bool,
+        /// Dump the session file's JSONL lines as stored on disk
+        #[arg(long, conflicts_with = "json")]
+        raw: bool,
+        /// With --raw, pretty-print each JSON line
+        #[arg(long, requires = "raw")]
+        pretty: bool,
+        /// Stamp each turn with the time it was recorded
+        #[arg(long)]
+        timestamps: bool,
+        /// Remove ANSI escape sequences (e.g. colored build output) from message text
+        #[arg(long)]
+        strip_ansi: bool,
+        /// Show tool commands, thinking, and errors in full instead of shortened
+        #[arg(long)]
+        no_truncate: bool,
+        /// Hard-wrap lines to N columns (the terminal width when N is omitted)
+        #[arg(
+            long,
+            value_name = "N",
+            num_args = 0..=1,
+            default_missing_value = "0",
+            conflicts_with_all = ["json", "raw"]
+        )]
+        wrap_width: Option<usize>,
+        /// Fold each run of consecutive tool calls into one summary line
+        #[arg(long, conflicts_with_all = ["no_collapse", "tools_only"])]
+        collapse_tools: bool,
+        /// List every tool call even when `collapse_tools` is set in the config
+        #[arg(long)]
+        no_collapse: bool,
+        /// Count each message type (internal entries included) above the conversation
+        #[arg(long, conflicts_with_all = ["json", "raw"])]
+        stats: bool,
+        /// Show the session file in the file manager instead of printing the conversation
+        #[arg(long, conflicts_with_all = ["json", "raw", "output", "editor"])]
+        reveal: bool,
+    },
+    Search {
+        query: String,
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Let --project match the project name as a subsequence (acbk -> acme-backend)
+        #[arg(long, requires = "project")]
+        project_fuzzy: bool,
+        /// Only search sessions since YYYY-MM-DD or a relative duration (30m, 24h, 7d, 2w)
+        #[arg(short, long)]
+        since: Option<String>,
+        /// Only search sessions whose model contains this text (case-insensitive)
+        #[arg(long)]
+        model: Option<String>,
+        /// Maximum results (0 for no limit)
+        #[arg(short, long, default_value_t = 50)]
+        max: usize,
+        /// Split the query on whitespace and require all or any of the terms
+        /// (default: match the whole query as one regex)
+        #[arg(long = "match", value_enum)]
+        match_mode: Option<MatchMode>,
+        /// Only search this session (short IDs work), listing every matching message
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Show the prompts recorded for a session in history.jsonl
+    History { session_id: String },
+    /// Show resolved paths and session counts, and report session files on
+    /// disk that no history entry points at
+    Doctor,
+    /// Move session files into the source's archived_sessions directory
+    Archive {
+        #[arg(required = true)]
+        session_ids: Vec<String>,
+        /// Skip the confirmation prompt (for scripts)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Delete session files and hide the sessions from listings
+    Delete {
+        #[arg(required = true)]
+        session_ids: Vec<String>,
+        /// Skip the confirmation prompt (for scripts)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Write every matching session to DIR/<project>/<session_id>.md (or .json)
+    ExportAll {
+        dir: PathBuf,
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// Only sessions since YYYY-MM-DD or a relative duration (30m, 24h, 7d, 2w)
+        #[arg(short, long)]
+        since: Option<String>,
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Resume the most recent session (optionally within a project or source)
+    #[command(alias = "-")]
+    Last {
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Only consider sessions from this source (claude or codex)
+        #[arg(long, value_parser = SessionSource::from_arg)]
+        source: Option<SessionSource>,
+        /// Print the resume command, directory, and model args instead of running them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Resume a session by ID without opening the TUI
+    Resume {
+        session_id: String,
+        /// Resume in this directory instead of the session's recorded project path
+        #[arg(long)]
+        cwd: Option<PathBuf>,
+        /// Recreate the project directory if it no longer exists
+        #[arg(long)]
+        create_cwd: bool,
+        /// Print the resume command, directory, and model args instead of running them
+        #[arg(long)]
+        dry_run: bool,
+        /// Resume with the newest model used for this source instead of the session's own
+        #[arg(long)]
+        model_latest: bool,
+    },
+    Stats {
+        /// Estimate spend per source from message sizes (reads every session file)
+        #[arg(long)]
+        cost: bool,
+        /// Count user prompts and assistant replies per source (reads every session file)
+        #[arg(long)]
+        turns: bool,
+        /// Days of daily activity to show; windows over 14 days render as a heatmap
+        #[arg(
+            long,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new()
+                .range(0..=MAX_STATS_DAYS as u64)
+        )]
+        days: Option<usize>,
+        /// Group model variants that differ only by date/version suffixes
+        #[arg(long)]
+        group_models: bool,
+        /// Draw with plain ASCII (automatic when the locale isn't UTF-8)
+        #[arg(long)]
+        ascii: bool,
+    },
+    List {
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Let --project match the project name as a subsequence (acbk -> acme-backend)
+        #[arg(long, requires = "project")]
+        project_fuzzy: bool,
+        /// Only list sessions since YYYY-MM-DD or a relative duration (30m, 24h, 7d, 2w)
+        #[arg(short, long)]
+        since: Option<String>,
+        /// Only list sessions that started at or after the given session
+        #[arg(long)]
+        since_session: Option<String>,
+        /// Hide sessions with fewer than N user/assistant messages (reads session files)
+        #[arg(long)]
+        min_messages: Option<usize>,
+        /// Only list sessions carrying this tag (assigned with `t` in the TUI)
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only list sessions whose model contains this text (case-insensitive)
+        #[arg(long)]
+        model: Option<String>,
+        /// Debug: list likely-duplicate sessions instead of collapsing them
+        #[arg(long)]
+        show_duplicates: bool,
+        /// Maximum sessions to list (0 for no limit)
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+        /// List every matching session, ignoring --limit
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+        #[arg(long, conflicts_with_all = ["format", "json_lines"])]
+        json: bool,
+        /// Stream one JSON object per line (shorthand for `--format json-lines`)
+        #[arg(long, conflicts_with = "format")]
+        json_lines: bool,
+        /// Output format (`--json` is shorthand for `--format json`)
+        #[arg(long, value_enum)]
+        format: Option<ListFormat>,
+        /// Comma-separated fields for JSON/CSV output, e.g. session_id,project,model
+        #[arg(long)]
+        fields: Option<String>,
+        /// Omit the table header and separator (for awk/cut)
+        #[arg(long)]
+        compact: bool,
+        /// Print session counts per model, project, or source instead of rows
+        #[arg(long, value_enum, conflicts_with_all = ["json", "json_lines", "format", "fields"])]
+        count_by: Option<CountBy>,
+        /// Append each project's current git branch (also `show_git_branch` in config)
+        #[arg(long)]
+        show_branch: bool,
+        /// Include sessions moved to archived_sessions (marked "(archived)")
+        #[arg(long)]
+        archived: bool,
+        /// Add a column with how long each session ran (reads session files)
+        #[arg(long)]
+        duration: bool,
+        /// Order rows by recency (default), age, project, model, or last resume
+        #[arg(long, value_enum, default_value_t)]
+        sort: SessionSort,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = SessionStore::new();
+
+    match cli.command {
+        None => {
+            if let Err(err) = run_tui(cli.watch, cli.archived) {
+                restore_terminal();
+                return Err(err);
+            }
+        }
+        Some(Commands::View {
+            session_id,
+            thinking,
+            tail,
+            head,
+            reverse,
+            no_pager,
+            show_internal,
+            json,
+            output,
+            editor,
+            no_tools,
+            tools_only,
+            raw,
+            pretty,
+            timestamps,
+            strip_ansi,
+            no_truncate,
+            wrap_width,
+            collapse_tools,
+            no_collapse,
+            stats,
+            reveal,
+        }) => {
+            let session_path = Path::new(&session_id);
+            let session = if session_path.is_file() {
+                store.session_from_file(session_path)
+            } else {
+                store.resolve_session_arg(&session_id)?
+            };
+            if reveal {
+                let path = reveal_session_file(&session)?;
+                eprintln!("Revealed {}", path.display());
+                return Ok(());
+            }
+            let options = RenderOptions {
+                thinking,
+                tail,
+                head,
+                show_internal,
+                reverse,
+                no_tools,
+                tools_only,
+                timestamps,
+                strip_ansi,
+                no_truncate,
+                collapse_tools: collapse_tools || (config().collapse_tools && !no_collapse),
+                stats,
+            };
+            let rendered = if raw {
+                raw_session_lines(&session, pretty)?
+            } else if json {
+                let value = render_conversation_json(&store, &session, options);
+                serde_json::to_string_pretty(&value)?
+            } else {
+                let rendered = render_conversation(&store, &session, options).join("\n");
+                match wrap_width {
+                    Some(0) => wrap_text(&rendered, default_wrap_width()),
+                    Some(width) => wrap_text(&rendered, width),
+                    None => rendered,
+                }
+            };
+            if let Some(editor) = editor {
+                let extension = if json { "json" } else { "txt" };
+                return open_in_editor(&rendered, &session.session_id, extension, &editor);
+            }
+            match output {
+                Some(path) => write_output_file(&path, &rendered)?,
+                None if json => println!("{rendered}"),
+                None => output_with_optional_pager(&rendered, no_pager)?,
+            }
+        }
+        Some(Commands::Search {
+            query,
+            project,
+            project_fuzzy,
+            since,
+            model,
+            max,
+            match_mode,
+            session,
+        }) => {
+            let options = SearchOptions {
+                project: resolve_project_filter(project)?,
+                project_fuzzy,
+                since_ms: since.as_deref().map(parse_since_ms).transpose()?,
+                model,
+                max_results: max,
+                match_mode,
+                session: session
+                    .as_deref()
+                    .map(|id| store.resolve_session_arg(id))
+                    .transpose()?,
+            };
+            let results = store.search(&query, &options)?;
+            println!("{}", render_search_results(results));
+        }
+        Some(Commands::Archive { session_ids, yes }) => {
+            let sessions = session_ids
+                .iter()
+                .map(|id| resolve_destructive_arg(&mut store, id, "archive"))
+                .collect::<Result<Vec<_>>>()?;
+            if !yes && !confirm_action("Archive", &sessions)? {
+                return Ok(());
+            }
+            for session in &sessions {
+                let dest = store.archive_session(session)?;
+                println!("archived {} -> {}", session.session_id, dest.display());
+            }
+        }
+        Some(Commands::Delete { session_ids, yes }) => {
+            let sessions = session_ids
+                .iter()
+                .map(|id| resolve_destructive_arg(&mut store, id, "delete"))
+                .collect::<Result<Vec<_>>>()?;
+            if !yes && !confirm_action("Delete", &sessions)? {
+                return Ok(());
+            }
+            for session in &sessions {
+                store.delete_session(session)?;
+                println!("deleted {}", session.session_id);
+            }
+        }
+        Some(Commands::ExportAll {
+            dir,
+            format,
+            since,
+            project,
+        }) => {
+            let filters = ListFilters {
+                project: resolve_project_filter(project)?,
+                since,
+                ..ListFilters::default()
+            };
+            let sessions = filter_sessions(&mut store, &filters)?;
+            let (exported, skipped) = export_sessions(&store, &sessions, &dir, format);
+            let skipped = if skipped > 0 {
+                format!(" ({skipped} skipped)")
+            } else {
+                String::new()
+            };
+            println!(
+                "Exported {exported} session(s) to {}{skipped}",
+                dir.display()
+            );
+        }
+        Some(Commands::Doctor) => {
+            println!("{}\n", render_doctor_report(&store.doctor_report()));
+            println!("{}", render_orphaned_files(&store.orphaned_session_files()));
+        }
+        Some(Commands::History { session_id }) => {
+            let (source, full_id, entries) =
+                SessionStore::history_entries_for_session(&session_id)?;
+            print!("{}", render_history_entries(source, &full_id, &entries));
+        }
+        Some(Commands::Resume {
+            session_id,
+            cwd,
+            create_cwd,
+            dry_run,
+            model_latest,
+        }) => {
+            let session = store.resolve_session_arg(&session_id)?;
+            resume_session(
+                &mut store,
+                &session,
+                &ResumeOptions {
+                    cwd,
+                    create_cwd,
+                    dry_run,
+                    latest_model: model_latest,
+                },
+            )?;
+        }
+        Some(Commands::Last {
+            project,
+            source,
+            dry_run,
+        }) => {
+            let filters = ListFilters {
+                project: resolve_project_filter(project)?,
+                source,
+                ..ListFilters::default()
+            };
+            let session = filter_sessions(&mut store, &filters)?
+                .into_iter()
+                .next()
+                .context("no sessions match")?;
+            resume_session(
+                &mut store,
+                &session,
+                &ResumeOptions {
+                    dry_run,
+                    ..ResumeOptions::default()
+                },
+            )?;
+        }
+        Some(Commands::Stats {
+            cost,
+            turns,
+            days,
+            group_models,
+            ascii,
+        }) => {
+            let days = days
+                .filter(|d| *d > 0)
+                .unwrap_or_else(|| config().stats_days());
+            let stats = store.build_stats_report(cost, turns, days, group_models);
+            println!("{}", render_stats(&stats, StatsGlyphs::detect(ascii)));
+        }
+        Some(Commands::List {
+            project,
+            project_fuzzy,
+            since,
+            since_session,
+            min_messages,
+            tag,
+            model,
+            show_duplicates,
+            limit,
+            all,
+            json,
+            json_lines,
+            format,
+            fields,
+            compact,
+            count_by,
+            show_branch,
+            archived,
+            duration,
+            sort,
+        }) => {
+            store.keep_duplicates = show_duplicates;
+            let filters = ListFilters {
+                project: resolve_project_filter(project)?,
+                since,
+                tag,
+                model,
+                since_session,
+                min_messages,
+                project_fuzzy,
+                source: None,
+                archived,
+            };
+            let limit = if all { usize::MAX } else { limit };
+            let format = if json {
+                ListFormat::Json
+            } else if json_lines {
+                ListFormat::JsonLines
+            } else {
+                format.unwrap_or_default()
+            };
+            let fields = match fields.as_deref() {
+                Some(_) if format == ListFormat::Table => {
+                    return Err(anyhow!(
+                        "--fields applies to --json, --json-lines, and --format csv"
+                    ));
+                }
+                Some(spec) => parse_list_fields(spec)?,
+                None => Vec::new(),
+            };
+            if compact && format != ListFormat::Table {
+                return Err(anyhow!("--compact applies to the table format"));
+            }
+            if duration && format != ListFormat::Table {
+                return Err(anyhow!("--duration applies to the table format"));
+            }
+            if let Some(count_by) = count_by {
+                let sessions = filter_sessions(&mut store, &filters)?;
+                println!("{}", render_session_counts(&mut store, sessions, count_by));
+                return Ok(());
+            }
+            if format == ListFormat::JsonLines {
+                let mut sessions = filter_sessions(&mut store, &filters)?;
+                store.sort_for_list(&mut sessions, sort);
+                write_sessions_json_lines(&sessions, limit, &fields, &mut stdout().lock())?;
+            } else {
+                let output = list_command(
+                    &mut store,
+                    &filters,
+                    limit,
+                    format,
+                    &fields,
+                    sort,
+                    TableOptions {
+                        compact,
+                        show_branch: show_branch || config().show_git_branch,
+                        show_duration: duration,
+                    },
+                )?;
+                println!("{}", output);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the sessions and asks for a y/N answer on the terminal. Without a
+/// terminal, refuses and points at `--yes`.
+fn confirm_action(action: &str, sessions: &[SessionInfo]) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "{action} needs confirmation; pass --yes when running non-interactively"
+        ));
+    }
+    for session in sessions {
+        eprintln!(
+            "  {}  {}  {}",
+            session.short_id(),
+            short_project(&session.project),
+            truncate(&session.display, 60)
+        );
+    }
+    eprint!("{action} {} session(s)? [y/N] ", sessions.len());
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// The session file's lines verbatim (or each JSON object pretty-printed),
+/// for diagnosing sessions that render oddly.
+fn raw_session_lines(session: &SessionInfo, pretty: bool) -> Result<String> {
+    let path = session
+        .file_path
+        .as_deref()
+        .with_context(|| format!("No session file found for {}", session.session_id))?;
+    let reader =
+        open_session_file(Path::new(path)).with_context(|| format!("failed to read {path}"))?;
+    let mut out = Vec::new();
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("failed to read {path}"))?;
+        let formatted = if pretty {
+            serde_json::from_str::<Value>(&line)
+                .ok()
+                .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                .unwrap_or(line)
+        } else {
+            line
+        };
+        out.push(formatted);
+    }
+    Ok(out.join("\n"))
+}
+
+fn write_output_file(path: &Path, output: &str) -> Result<()> {
+    fs::write(path, format!("{output}\n"))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn output_with_optional_pager(output: &str, no_pager: bool) -> Result<()> {
+    if no_pager || !io::stdout().is_terminal() {
+        println!("{output}");
+        return Ok(());
+    }
+
+    let mut proc = match Command::new("less")
+        .arg("-R")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .spawn()
+    {
+        Ok(proc) => proc,
+        Err(_) => {
+            println!("{output}");
+            return Ok(());
+        }
+    };
+
+    if let Some(mut stdin) = proc.stdin.take() {
+        if let Err(err) = stdin.write_all(output.as_bytes()) {
+            let _ = err;
+            println!("{output}");
+            return Ok(());
+        }
+    }
+    proc.wait().with_context(|| "pager exited with error")?;
+
+    Ok(())
+}
+
+/// Writes `text` to a temp file, opens it in `editor` (or `$VISUAL` /
+/// `$EDITOR` / `vi` when empty), and removes the file once the editor exits.
+/// Creates `path` readable only by the user and writes `text` to it. Fails if
+/// anything (including a planted symlink) already exists there.
+fn write_private_file(path: &Path, text: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+fn open_in_editor(text: &str, session_id: &str, extension: &str, editor: &str) -> Result<()> {
+    let editor = Some(editor.trim().to_string())
+        .filter(|editor| !editor.is_empty())
+        .or_else(|| env::var("VISUAL").ok().filter(|v| !v.trim().is_empty()))
+        .or_else(|| env::var("EDITOR").ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string());
+    let path = env::temp_dir().join(format!(
+        "cs-rs-{}-{}.{extension}",
+        session_id.replace(['/', '\\'], "_"),
+        std::process::id()
+    ));
+    write_private_file(&path, text)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    // Through the shell so editors configured with flags (`code -w`) work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let _ = fs::remove_file(&path);
+    let status = status.with_context(|| format!("failed to start editor: {editor}"))?;
+    if !status.success() {
+        return Err(anyhow!("editor exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Clipboard commands tried in order; the first one that runs wins.
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Program and arguments that show `path` in the platform's file manager:
+/// selected in Finder or Explorer, or its folder opened via `xdg-open`.
+fn reveal_command(path: &Path) -> (&'static str, Vec<OsString>) {
+    if cfg!(target_os = "macos") {
+        ("open", vec!["-R".into(), path.into()])
+    } else if cfg!(windows) {
+        let mut select = OsString::from("/select,");
+        select.push(path);
+        ("explorer", vec![select])
+    } else {
+        let dir = path.parent().unwrap_or(path);
+        ("xdg-open", vec![dir.into()])
+    }
+}
+
+fn reveal_session_file(session: &SessionInfo) -> Result<PathBuf> {
+    let path = session
+        .file_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+        .with_context(|| format!("No session file found for {}", session.session_id))?;
+    let (program, args) = reveal_command(&path);
+    let status = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("failed to run {program}"))?;
+    // explorer.exe exits non-zero even when the window opens.
+    if !status.success() && program != "explorer" {
+        return Err(anyhow!("{program} exited with {status}"));
+    }
+    Ok(path)
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let Ok(mut proc) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = proc.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if proc.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(anyhow!(
+        "no clipboard command available (tried pbcopy, wl-copy, xclip, xsel, clip.exe)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes tests that point `CS_RS_DATA_DIR` at a fixture root.
+    static DATA_DIR_ENV: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn test_store() -> SessionStore {
+        SessionStore {
+            sessions: HashMap::new(),
+            loaded: true,
+            cache: SessionCache {
+                version: 1,
+                ..SessionCache::default()
+            },
+            cache_dirty: false,
+            search_text_cache: HashMap::new(),
+            keep_duplicates: false,
+            load_counts: HashMap::new(),
+        }
+    }
+
+    /// A session with only the fields most tests care about; override the
+    /// rest with struct update syntax.
+    fn test_session(id: &str, source: SessionSource, timestamp: i64) -> SessionInfo {
+        SessionInfo {
+            source,
+            session_id: id.to_string(),
+            display: String::new(),
+            project: String::new(),
+            timestamp,
+            model: String::new(),
+            reasoning_effort: String::new(),
+            file_path: None,
+        }
+    }
+
+    #[test]
+    fn get_exact_falls_back_to_recent_source_model() {
+        let source = SessionSource::Claudecode;
+        let recent = SessionInfo {
+            source,
+            session_id: "recent-session".to_string(),
+            display: "recent".to_string(),
+            project: "/tmp/project".to_string(),
+            timestamp: 2_000,
+            model: "claude-opus-4-6".to_string(),
+            reasoning_effort: String::new(),
+            file_path: Some("/tmp/recent.jsonl".to_string()),
+        };
+        let target = SessionInfo {
+            source,
+            session_id: "target-session".to_string(),
+            display: "target".to_string(),
+            project: "/tmp/project".to_string(),
+            timestamp: 1_000,
+            model: String::new(),
+            reasoning_effort: String::new(),
+            file_path: Some("/tmp/target.jsonl".to_string()),
+        };
+
+        let mut store = test_store();
+        store
+            .sessions
+            .insert(source.internal_key(&recent.session_id), recent);
+        store
+            .sessions
+            .insert(source.internal_key(&target.session_id), target);
+
+        let resolved = store
+            .get_exact(source, "target-session")
+            .expect("target session should resolve");
+        assert_eq!(resolved.model, "claude-opus-4-6");
+    }
+
+    #[test]
+    fn render_stats_outputs_separate_source_sections() {
+        let report = StatsReport {
+            total_sessions: 2,
+            total_history_entries: 3,
+            last_computed_date: "2026-02-13".to_string(),
+            days: 14,
+            sources: vec![
+                StatsSourceRow {
+                    source: SessionSource::Claudecode,
+                    sessions: 1,
+                    history_entries: 2,
+                    first_session_date: "2026-02-01".to_string(),
+                    top_models: vec![("claude-opus-4-6".to_string(), 1)],
+                    daily_sessions: vec![("2026-02-13".to_string(), 1)],
+                    estimated_cost_usd: None,
+                    turns: Some((4, 10)),
+                },
+                StatsSourceRow {
+                    source: SessionSource::Codex,
+                    sessions: 1,
+                    history_entries: 1,
+                    first_session_date: "2026-02-02".to_string(),
+                    top_models: vec![("gpt-5.2-codex".to_string(), 1)],
+                    daily_sessions: vec![("2026-02-13".to_string(), 1)],
+                    estimated_cost_usd: None,
+                    turns: None,
+                },
+            ],
+        };
+
+        let rendered = render_stats(&report, &StatsGlyphs::UNICODE);
+        assert!(rendered.contains("CLAUDE CODE:"));
+        assert!(rendered.contains("CODEX:"));
+        assert!(rendered.contains("claude-opus-4-6"));
+        assert!(rendered.contains("gpt-5.2-codex"));
+        assert!(rendered.contains("  Turns: 4 user / 10 assistant (2.5 assistant per user)\n"));
+        assert_eq!(rendered.matches("  Turns:").count(), 1);
+
+        let ascii = render_stats(&report, &StatsGlyphs::ASCII);
+        assert!(ascii.is_ascii());
+        assert!(ascii.starts_with("+---"));
+        assert!(ascii.contains("2026-02-13      1 ########################"));
+    }
+
+    #[test]
+    fn cost_rate_prefers_longest_matching_prefix() {
+        let mut config = Config::default();
+        assert_eq!(config.cost_rate_for_model("claude-opus-4-6"), Some(0.045));
+        assert_eq!(config.cost_rate_for_model("unknown-model"), None);
+
+        config
+            .cost_rates
+            .insert("claude-opus-4-6".to_string(), 0.01);
+        assert_eq!(
+            config.cost_rate_for_model("claude-opus-4-6[1m]"),
+            Some(0.01)
+        );
+        assert_eq!(config.cost_rate_for_model("claude-opus-4-5"), Some(0.045));
+    }
+
+    #[test]
+    fn codex_model_candidate_normalizes_effort_suffix() {
+        assert_eq!(
+            codex_model_candidate("gpt-5.3-codex high").as_deref(),
+            Some("gpt-5.3-codex")
+        );
+        assert_eq!(
+            codex_model_candidate("  gpt-5.3-codex   medium ").as_deref(),
+            Some("gpt-5.3-codex")
+        );
+    }
+
+    #[test]
+    fn codex_effort_candidate_normalizes_case() {
+        assert_eq!(codex_effort_candidate("HIGH").as_deref(), Some("high"));
+        assert_eq!(codex_effort_candidate("xhigh").as_deref(), Some("xhigh"));
+        assert_eq!(codex_effort_candidate(""), None);
+        assert_eq!(codex_effort_candidate("high effort"), None);
+    }
+
+    #[test]
+    fn codex_file_info_extracts_model_and_effort() {
+        let session_id = "019c24fb-6f78-7a20-99d0-88871c381f5d";
+        let file_name = format!(
+            "cs-rs-codex-info-test-{}-{}.jsonl",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        let path = env::temp_dir().join(file_name);
+        let fixture = format!(
+            "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{session_id}\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"cwd\":\"/tmp/demo\"}}}}\n\
+{{\"type\":\"turn_context\",\"payload\":{{\"model\":\"gpt-5.3-codex high\",\"effort\":\"HIGH\",\"collaboration_mode\":{{\"settings\":{{\"reasoning_effort\":\"medium\"}}}}}}}}\n"
+        );
+        fs::write(&path, fixture).expect("failed to write fixture file");
+
+        let store = test_store();
+        let info = store
+            .codex_file_info_from_session_file(&path, session_id)
+            .expect("expected codex file info");
+
+        assert_eq!(info.model.as_deref(), Some("gpt-5.3-codex"));
+        assert_eq!(info.reasoning_effort.as_deref(), Some("high"));
+        assert_eq!(info.cwd.as_deref(), Some("/tmp/demo"));
+        assert_eq!(info.timestamp_ms, Some(1_771_002_000_000));
+        assert_eq!(info.title, None);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn session_from_file_detects_codex_transcripts() {
+        let session_id = "019c24fb-6f78-7a20-99d0-88871c381f5e";
+        let dir = env::temp_dir().join(format!(
+            "cs-rs-view-path-test-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(&dir).expect("failed to create fixture dir");
+        let path = dir.join(format!("rollout-2026-02-13T17-00-00-{session_id}.jsonl"));
+        let fixture = format!(
+            "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{session_id}\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"cwd\":\"/tmp/archived\"}}}}\n\
+{{\"type\":\"response_item\",\"payload\":{{\"type\":\"message\",\"role\":\"user\",\"content\":[{{\"type\":\"input_text\",\"text\":\"recover me\"}}]}}}}\n"
+        );
+        fs::write(&path, fixture).expect("failed to write fixture file");
+
+        let store = test_store();
+        let session = store.session_from_file(&path);
+        assert_eq!(session.source, SessionSource::Codex);
+        assert_eq!(session.session_id, session_id);
+        assert_eq!(session.project, "/tmp/archived");
+        assert_eq!(session.display, "recover me");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn parse_codex_message_maps_tool_calls_and_outputs() {
+        let call = parse_codex_message(
+            r#"{"type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"ls\",\"-la\"]}","call_id":"call_1"}}"#,
+        )
+        .expect("function_call should parse");
+        assert_eq!(call.msg_type, "assistant");
+        let block = &call.content_blocks()[0];
+        assert_eq!(block["type"], "tool_use");
+        assert_eq!(block["id"], "call_1");
+        assert_eq!(block["input"]["command"][1], "-la");
+
+        let output = parse_codex_message(
+            r#"{"type":"response_item","payload":{"type":"function_call_output","call_id":"call_1","output":"total 0"}}"#,
+        )
+        .expect("function_call_output should parse");
+        assert_eq!(output.msg_type, "user");
+        assert_eq!(
+            tool_result_preview(&output.content_blocks()[0]).as_deref(),
+            Some("total 0")
+        );
+    }
+
+    #[test]
+    fn resolve_resume_cwd_only_recreates_missing_project_when_asked() {
+        let missing = env::temp_dir().join(format!(
+            "cs-rs-missing-project-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let session = SessionInfo {
+            display: "resume".to_string(),
+            project: missing.to_string_lossy().to_string(),
+            ..test_session("resume-session", SessionSource::Claudecode, 1)
+        };
+
+        let elsewhere = ResumeOptions {
+            cwd: Some(env::temp_dir()),
+            ..ResumeOptions::default()
+        };
+        assert_eq!(
+            resolve_resume_cwd(&session, &elsewhere).expect("override should resolve"),
+            env::temp_dir()
+        );
+        assert!(!missing.exists());
+
+        let create = ResumeOptions {
+            create_cwd: true,
+            ..ResumeOptions::default()
+        };
+        // Planning, as `--dry-run` does, leaves the directory alone.
+        assert_eq!(
+            plan_resume_cwd(&session, &create).unwrap(),
+            ResumeCwd::Create(missing.clone())
+        );
+        assert!(matches!(
+            plan_resume_cwd(&session, &ResumeOptions::default()).unwrap(),
+            ResumeCwd::Prompt { .. }
+        ));
+        assert!(!missing.exists());
+        assert_eq!(
+            resolve_resume_cwd(&session, &create).expect("create_cwd should resolve"),
+            missing
+        );
+        assert!(missing.is_dir());
+
+        let _ = fs::remove_dir_all(missing);
+    }
+
+    #[test]
+    fn collapse_duplicate_sessions_prefers_entry_with_file() {
+        let session = |id: &str, ts: i64, file_path: Option<String>| SessionInfo {
+            display: "Fix   the flaky test".to_string(),
+            project: "/tmp/project".to_string(),
+            file_path,
+            ..test_session(id, SessionSource::Codex, ts)
+        };
+        let file = env::temp_dir().join(format!(
+            "cs-rs-dup-test-{}-{}.jsonl",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let transcript = "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n";
+        fs::write(&file, transcript).expect("failed to write fixture file");
+
+        let other_file = file.with_extension("other.jsonl");
+        fs::write(&other_file, transcript).expect("failed to write fixture file");
+
+        let mut seen = HashMap::new();
+        let mut old_format = session("old-format", 1_000_000, None);
+        old_format.model = "gpt-5.2-codex".to_string();
+        for s in [
+            old_format,
+            session(
+                "new-format",
+                1_060_000,
+                Some(file.to_string_lossy().to_string()),
+            ),
+            session(
+                "restarted",
+                1_120_000,
+                Some(other_file.to_string_lossy().to_string()),
+            ),
+            session("next-day", 90_000_000, None),
+        ] {
+            seen.insert(s.source.internal_key(&s.session_id), s);
+        }
+
+        SessionStore::collapse_duplicate_sessions(&mut seen);
+        let mut ids: Vec<_> = seen.values().map(|s| s.session_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["new-format", "next-day", "restarted"]);
+        let merged = seen
+            .values()
+            .find(|s| s.session_id == "restarted")
+            .expect("newest resumable session should absorb metadata");
+        assert_eq!(merged.model, "gpt-5.2-codex");
+
+        let _ = fs::remove_file(file);
+        let _ = fs::remove_file(other_file);
+    }
+
+    #[test]
+    fn codex_title_candidate_skips_injected_context() {
+        assert_eq!(
+            codex_title_candidate("<environment_context>\n<cwd>/tmp</cwd>"),
+            None
+        );
+        assert_eq!(
+            codex_title_candidate("\n  Refactor the parser\nand add tests").as_deref(),
+            Some("Refactor the parser")
+        );
+    }
+
+    #[test]
+    fn session_id_hex_tail_uses_last_five_hex_chars() {
+        let id = "019c24fb-6f78-7a20-99d0-88871c381f5d";
+        assert_eq!(session_id_hex_tail(id, 5), "81f5d");
+    }
+
+    #[test]
+    fn apply_filter_matches_cc_source_alias() {
+        let mut store = test_store();
+        let sessions = vec![
+            SessionInfo {
+                source: SessionSource::Claudecode,
+                session_id: "a".to_string(),
+                display: "one".to_string(),
+                project: "/tmp/a".to_string(),
+                timestamp: 1,
+                model: String::new(),
+                reasoning_effort: String::new(),
+                file_path: None,
+            },
+            SessionInfo {
+                source: SessionSource::Codex,
+                session_id: "b".to_string(),
+                display: "two".to_string(),
+                project: "/tmp/b".to_string(),
+                timestamp: 1,
+                model: String::new(),
+                reasoning_effort: String::new(),
+                file_path: None,
+            },
+        ];
+        let mut filtered = Vec::new();
+        apply_filter(&mut store, &mut filtered, &sessions, "cc");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].source, SessionSource::Claudecode);
+    }
+
+    #[test]
+    fn apply_filter_matches_full_text_in_session_messages() {
+        let mut store = test_store();
+        let session_id = "fulltext-session";
+        let file_name = format!(
+            "cs-rs-fulltext-filter-test-{}-{}.jsonl",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        let path = env::temp_dir().join(file_name);
+        let fixture = format!(
+            "{{\"type\":\"user\",\"uuid\":\"u1\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"isApiErrorMessage\":false,\"sessionId\":\"{session_id}\",\"message\":{{\"role\":\"user\",\"content\":[{{\"type\":\"text\",\"text\":\"this includes flibbertigibbet\"}}]}}}}\n"
+        );
+        fs::write(&path, fixture).expect("failed to write fixture file");
+
+        let sessions = vec![SessionInfo {
+            source: SessionSource::Claudecode,
+            session_id: session_id.to_string(),
+            display: "session".to_string(),
+            project: "/tmp/fulltext".to_string(),
+            timestamp: 1,
+            model: String::new(),
+            reasoning_effort: String::new(),
+            file_path: Some(path.to_string_lossy().to_string()),
+        }];
+
+        let mut filtered = Vec::new();
+        apply_filter(&mut store, &mut filtered, &sessions, "flibbertigibbet");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(store.search_text_cache.len(), 1);
+
+        apply_filter(&mut store, &mut filtered, &sessions, "flibber");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(store.search_text_cache.len(), 1);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn jump_to_detail_match_cycles_through_matches() {
+        let lines = vec![
+            "Session: demo".to_string(),
+            "You: fix the parser".to_string(),
+            String::new(),
+            "Claude: the PARSER is fixed".to_string(),
+        ];
+        let matches = detail_match_lines(&lines, "parser");
+        assert_eq!(matches, vec![1, 3]);
+
+        let mut scroll = 0;
+        let mut current = None;
+        jump_to_detail_match(&matches, &mut scroll, &mut current, true);
+        assert_eq!((scroll, current), (1, Some(0)));
+        jump_to_detail_match(&matches, &mut scroll, &mut current, true);
+        assert_eq!((scroll, current), (3, Some(1)));
+        jump_to_detail_match(&matches, &mut scroll, &mut current, true);
+        assert_eq!((scroll, current), (1, Some(0)));
+        jump_to_detail_match(&matches, &mut scroll, &mut current, false);
+        assert_eq!((scroll, current), (3, Some(1)));
+    }
+
+    #[test]
+    fn list_time_formats_relative_days() {
+        let now = Local::now();
+
+        // 3 minutes 30 seconds → "3m 30s"
+        let ts = now - chrono::Duration::seconds(210);
+        assert_eq!(list_time(ts.timestamp_millis()), "3m 30s");
+
+        // 6 hours 23 minutes → "6h 23m"
+        let ts = now - chrono::Duration::minutes(383);
+        assert_eq!(list_time(ts.timestamp_millis()), "6h 23m");
+
+        // 3 hours 2 minutes → "3h 02m"
+        let ts = now - chrono::Duration::minutes(182);
+        assert_eq!(list_time(ts.timestamp_millis()), "3h 02m");
+
+        // 2 days and 5 hours → "2d 05h"
+        let ts = now - chrono::Duration::hours(53);
+        assert_eq!(list_time(ts.timestamp_millis()), "2d 05h");
+
+        // 10 days → "10d"
+        let ts = now - chrono::Duration::days(10);
+        assert_eq!(list_time(ts.timestamp_millis()), "10d");
+    }
+
+    #[test]
+    fn parse_since_ms_accepts_relative_durations_and_dates() {
+        let now_ms = Local::now().timestamp_millis();
+
+        let day_ago = parse_since_ms("24h").expect("24h should parse");
+        assert!((now_ms - day_ago - 86_400_000).abs() < 5_000);
+
+        let week_ago = parse_since_ms("7d").expect("7d should parse");
+        assert!((now_ms - week_ago - 7 * 86_400_000).abs() < 5_000);
+
+        assert!(parse_since_ms("2026-02-13").is_ok());
+        assert!(parse_since_ms("7x").is_err());
+        assert!(parse_since_ms("soon").is_err());
+    }
+
+    #[test]
+    fn codex_model_candidate_accepts_bracket_suffix() {
+        assert_eq!(
+            codex_model_candidate("claude-opus-4-6[1m]").as_deref(),
+            Some("claude-opus-4-6[1m]")
+        );
+        assert_eq!(
+            codex_model_candidate("claude-opus-4-6").as_deref(),
+            Some("claude-opus-4-6")
+        );
+    }
+
+    #[test]
+    fn resolve_project_filter_maps_dot_to_cwd() {
+        let cwd = fs::canonicalize(env::current_dir().unwrap()).unwrap();
+        assert_eq!(
+            resolve_project_filter(Some(".".to_string())).unwrap(),
+            Some(ProjectFilter::Exact(cwd.clone()))
+        );
+        assert_eq!(
+            resolve_project_filter(Some(String::new())).unwrap(),
+            Some(ProjectFilter::Exact(cwd.clone()))
+        );
+        assert_eq!(
+            resolve_project_filter(Some("crate".to_string())).unwrap(),
+            Some(ProjectFilter::Pattern("crate".to_string()))
+        );
+        assert_eq!(resolve_project_filter(None).unwrap(), None);
+
+        // The cwd filter matches that directory only, not a parent or a
+        // sibling that shares its name as a prefix.
+        let exact = ProjectFilter::Exact(cwd.clone());
+        let cwd_str = cwd.to_string_lossy().to_string();
+        assert!(exact.matches(&cwd_str, true));
+        assert!(exact.matches(&format!("{cwd_str}/"), true));
+        assert!(!exact.matches(&format!("{cwd_str}-old"), true));
+        assert!(!exact.matches(&format!("{cwd_str}/sub"), true));
+        if let Some(parent) = cwd.parent() {
+            assert!(!exact.matches(&parent.to_string_lossy(), true));
+        }
+        assert!(!exact.matches("", true));
+    }
+
+    #[test]
+    fn render_heatmap_shades_days_by_relative_count() {
+        let daily = vec![("2026-02-09".to_string(), 4), ("2026-02-13".to_string(), 1)];
+        // 2026-02-13 is a Friday; a 28-day window spans five week columns.
+        let lines = render_heatmap(&daily, "2026-02-13", 28, &StatsGlyphs::UNICODE);
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("Mon ") && lines[0].ends_with('█'));
+        assert!(lines[4].starts_with("Fri ") && lines[4].ends_with('░'));
+        assert!(lines[5].ends_with('·'));
+        assert_eq!(lines[0].chars().count(), 4 + 5);
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn truncate_keeps_grapheme_clusters_intact() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let text = format!("{family}{family}{family}");
+        assert_eq!(truncate(&text, 3), text);
+        assert_eq!(truncate(&text, 2), format!("{family}…"));
+        assert_eq!(truncate("cafe\u{301} au lait", 5), "cafe\u{301}…");
+    }
+
+    #[test]
+    fn fit_width_pads_and_truncates_by_display_width() {
+        assert_eq!(fit_width("abc", 5), "abc  ");
+        assert_eq!(fit_width("日本語", 8), "日本語  ");
+        assert_eq!(fit_width("日本語のパス", 6), "日本… ");
+        assert_eq!(fit_width("abc", 0), "");
+        assert_eq!(fit_width("abc", 1), "…");
+        assert_eq!(fit_width("日本語のパス", 6).width(), 6);
+    }
+
+    #[test]
+    fn land_on_first_match_skips_header_lines() {
+        let lines: Vec<String> = [
+            "Session: fix parser",
+            "Source: cc",
+            "",
+            "You: unrelated",
+            "",
+            "Claude: the parser bug is here",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let mut scroll = 0;
+        let mut current = None;
+        land_on_first_match(&lines, "parser", &mut scroll, &mut current);
+        assert_eq!(scroll, 5);
+        assert_eq!(current, Some(1));
+    }
+
+    #[test]
+    fn session_file_suffixes_include_compressed_archives() {
+        assert!(is_session_file(Path::new("/tmp/abc.jsonl")));
+        assert!(is_session_file(Path::new("/tmp/abc.jsonl.gz")));
+        assert!(is_session_file(Path::new("/tmp/abc.jsonl.zst")));
+        assert!(!is_session_file(Path::new("/tmp/abc.json.gz")));
+        assert_eq!(session_file_stem("rollout-x.jsonl.zst"), Some("rollout-x"));
+    }
+
+    #[test]
+    fn json_lines_writes_one_object_per_session() {
+        let session = |source, id: &str| SessionInfo {
+            display: "session".to_string(),
+            project: "/tmp/jsonl".to_string(),
+            ..test_session(id, source, 1)
+        };
+        let sessions = vec![
+            session(SessionSource::Claudecode, "aaaa-1"),
+            session(SessionSource::Codex, "bbbb-2"),
+        ];
+        let mut out = Vec::new();
+        write_sessions_json_lines(&sessions, 10, &[], &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["session_id"], "aaaa-1");
+        assert_eq!(first["source"], "claude code");
+    }
+
+    #[test]
+    fn rendered_lines_carry_the_role_of_their_turn() {
+        let dir = env::temp_dir().join(format!("cs-rs-detail-roles-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        let path = dir.join("roles.jsonl");
+        // A pasted transcript inside the prompt must stay a user turn.
+        fs::write(
+            &path,
+            "{\"type\":\"user\",\"uuid\":\"u1\",\"message\":{\"role\":\"user\",\"content\":\"look at this\\nClaude (m): fake\\n[tool] fake\\nError: fake\"}}\n\
+             {\"type\":\"assistant\",\"uuid\":\"a1\",\"message\":{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{\"type\":\"text\",\"text\":\"on it\\nYou: not a prompt\"},{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Bash\",\"input\":{\"command\":\"ls\"}}]}}\n",
+        )
+        .expect("write fixture");
+        let session = SessionInfo {
+            display: "look".to_string(),
+            project: "/tmp/p".to_string(),
+            file_path: Some(path.to_string_lossy().to_string()),
+            ..test_session("roles", SessionSource::Claudecode, 1)
+        };
+        let store = test_store();
+        let options = RenderOptions {
+            reverse: true,
+            ..RenderOptions::default()
+        };
+        let body: Vec<(DetailRole, String)> = render_conversation_lines(&store, &session, options)
+            .into_iter()
+            .skip_while(|(_, line)| !line.starts_with("You:"))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        let roles: Vec<DetailRole> = body.iter().map(|(role, _)| *role).collect();
+        assert_eq!(
+            roles,
+            [
+                DetailRole::User,
+                DetailRole::User,
+                DetailRole::User,
+                DetailRole::User,
+                DetailRole::Assistant,
+                DetailRole::Assistant,
+                DetailRole::Tool,
+            ],
+            "{body:?}"
+        );
+        assert_eq!(body[1].1, "Claude (m): fake");
+        assert_eq!(body[5].1, "You: not a prompt");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn collapse_path_keeps_first_initial_and_tail() {
+        assert_eq!(
+            collapse_path("~/work/acme/services/api/crate", 2),
+            "~/w/…/api/crate"
+        );
+        assert_eq!(collapse_path("/srv/a/b/c/crate", 1), "/s/…/crate");
+        assert_eq!(collapse_path("~/work/acme/crate", 2), "~/work/acme/crate");
+        assert_eq!(
+            collapse_path("~/work/acme/services/api/crate", 0),
+            "~/work/acme/services/api/crate"
+        );
+    }
+
+    #[test]
+    fn detail_turn_round_trips_through_rerender() {
+        let to_lines = |raw: &[&str]| raw.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let before = to_lines(&["Session: x", "", "Claude: a", "", "You: b", "more", ""]);
+        let after = to_lines(&[
+            "Session: x",
+            "",
+            "Claude: a",
+            "[thinking] hmm",
+            "",
+            "You: b",
+            "more",
+            "",
+        ]);
+        let turn = detail_turn_at(&before, 5);
+        assert_eq!(turn, 2);
+        assert_eq!(detail_turn_start(&after, turn), 5);
+        assert_eq!(detail_turn_start(&after, 0), 0);
+    }
+
+    #[test]
+    fn session_age_buckets_by_hour_and_day() {
+        let now = Local::now().timestamp_millis();
+        assert_eq!(session_age(now - 60_000), SessionAge::Fresh);
+        assert_eq!(session_age(now - 5 * 3_600_000), SessionAge::Recent);
+        assert_eq!(session_age(now - 3 * 86_400_000), SessionAge::Old);
+    }
+
+    #[test]
+    fn parse_list_fields_rejects_unknown_names() {
+        assert_eq!(
+            parse_list_fields("session_id, project").unwrap(),
+            vec!["session_id".to_string(), "project".to_string()]
+        );
+        assert!(parse_list_fields("session_id,colour").is_err());
+        assert!(parse_list_fields(" , ").is_err());
+    }
+
+    #[test]
+    fn display_zone_parses_utc_and_offsets() {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        assert_eq!(DisplayZone::parse("UTC"), Some(DisplayZone::Fixed(utc)));
+        assert_eq!(DisplayZone::parse("local"), Some(DisplayZone::Local));
+        let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        assert_eq!(DisplayZone::parse("+05:30"), Some(DisplayZone::Fixed(ist)));
+        assert_eq!(
+            DisplayZone::parse("UTC+0530"),
+            Some(DisplayZone::Fixed(ist))
+        );
+        let pst = FixedOffset::west_opt(8 * 3600).unwrap();
+        assert_eq!(DisplayZone::parse("-8"), Some(DisplayZone::Fixed(pst)));
+        assert_eq!(DisplayZone::parse("America/New_York"), None);
+        assert_eq!(DisplayZone::parse("+25:00"), None);
+
+        let ts = DisplayZone::Fixed(ist).convert(Utc.timestamp_millis_opt(0).unwrap());
+        assert_eq!(ts.format("%Y-%m-%d %H:%M").to_string(), "1970-01-01 05:30");
+    }
+
+    #[test]
+    fn model_family_strips_date_and_version_suffixes() {
+        assert_eq!(model_family("claude-opus-4-6-20260101"), "claude-opus-4-6");
+        assert_eq!(model_family("claude-opus-4-6[1m]"), "claude-opus-4-6");
+        assert_eq!(model_family("claude-opus-4-6"), "claude-opus-4-6");
+        assert_eq!(model_family("gpt-4.1-2025-04-14"), "gpt-4.1");
+        assert_eq!(
+            model_family("anthropic.claude-sonnet-4-5-20250929-v1:0"),
+            "anthropic.claude-sonnet-4-5"
+        );
+        assert_eq!(
+            model_family("claude-haiku-4-5@20251001"),
+            "claude-haiku-4-5"
+        );
+        assert_eq!(model_family("gpt-5"), "gpt-5");
+    }
 
-        match key.code {
-            KeyCode::Char('q') => break,
-            KeyCode::Char('/') => {
-                filter_input = true;
-                filter.clear();
-                refresh_filter_results(
-                    &mut store,
-                    &mut filtered,
-                    &sessions,
-                    &mut previous_filter,
-                    &filter,
-                );
-            }
-            KeyCode::Esc => break,
-            KeyCode::Up => {
-                let prev = match list_state.selected() {
-                    Some(0) | None => 0,
-                    Some(i) => i.saturating_sub(1),
-                };
-                list_state.select(Some(prev));
-            }
-            KeyCode::Down => {
-                let len = filtered.len();
-                let next = match list_state.selected() {
-                    Some(i) => {
-                        if i + 1 < len {
-                            i + 1
-                        } else {
-                            i
-                        }
-                    }
-                    None => 0,
-                };
-                list_state.select(Some(next));
-            }
-            KeyCode::Enter => {
-                let idx = list_state.selected().unwrap_or_default();
-                if idx < filtered.len() {
-                    let selected = &filtered[idx];
-                    let session = store
-                        .get_exact(selected.source, &selected.session_id)
-                        .unwrap_or_else(|| selected.clone());
-                    cleanup_terminal(&mut terminal)?;
-                    resume_session(&session)?;
-                    return Ok(());
-                }
-            }
-            _ => {}
-        }
+    #[test]
+    fn session_source_from_arg_accepts_aliases() {
+        assert_eq!(
+            SessionSource::from_arg("Claude"),
+            Ok(SessionSource::Claudecode)
+        );
+        assert_eq!(SessionSource::from_arg("cc"), Ok(SessionSource::Claudecode));
+        assert_eq!(SessionSource::from_arg("codex"), Ok(SessionSource::Codex));
+        assert!(SessionSource::from_arg("gemini").is_err());
     }
 
-    cleanup_terminal(&mut terminal)?;
-    Ok(())
-}
+    #[test]
+    fn load_reads_sessions_from_data_dir_override() {
+        let root = env::temp_dir().join(format!(
+            "cs-rs-data-dir-test-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let claude = root.join(".claude");
+        let project_dir = claude.join("projects").join("-tmp-fixture");
+        fs::create_dir_all(&project_dir).expect("failed to create fixture dir");
+        fs::write(
+            claude.join("history.jsonl"),
+            "{\"sessionId\":\"fixture-1\",\"display\":\"hello fixture\",\"timestamp\":1771002000000,\"project\":\"/tmp/fixture\"}\n",
+        )
+        .expect("failed to write history");
+        fs::write(
+            project_dir.join("fixture-1.jsonl"),
+            "{\"type\":\"user\",\"sessionId\":\"fixture-1\",\"message\":{\"role\":\"user\",\"content\":\"hello fixture\"}}\n",
+        )
+        .expect("failed to write transcript");
+
+        let _env = DATA_DIR_ENV.lock().unwrap_or_else(|err| err.into_inner());
+        env::set_var("CS_RS_DATA_DIR", &root);
+        let mut store = SessionStore::new();
+        let sessions = store.all();
+        env::remove_var("CS_RS_DATA_DIR");
+
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "fixture-1");
+        assert_eq!(sessions[0].display, "hello fixture");
+        assert!(sessions[0]
+            .file_path
+            .as_deref()
+            .is_some_and(|path| path.starts_with(root.to_string_lossy().as_ref())));
 
-fn apply_filter(
-    store: &mut SessionStore,
-    filtered: &mut Vec<SessionInfo>,
-    sessions: &[SessionInfo],
-    filter: &str,
-) {
-    let q = filter.to_lowercase();
-    if q.is_empty() {
-        filtered.clear();
-        filtered.extend_from_slice(sessions);
-        return;
+        let _ = fs::remove_dir_all(root);
     }
 
-    filtered.clear();
-    for session in sessions {
-        let source_label = session.source.label().to_lowercase();
-        let source_list_label = session.source.list_label().to_lowercase();
-        if session.display.to_lowercase().contains(&q)
-            || session.project.to_lowercase().contains(&q)
-            || session.session_id.to_lowercase().contains(&q)
-            || source_label.contains(&q)
-            || source_list_label.contains(&q)
-            || store.session_contains_full_text(session, &q)
-        {
-            filtered.push(session.clone());
-        }
+    #[test]
+    fn conversation_size_line_counts_turns_and_words() {
+        let msgs: Vec<Message> = [
+            r#"{"type":"user","message":{"role":"user","content":"list the files please"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Sure thing"}]}}"#,
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"a b c"}]}}"#,
+        ]
+        .iter()
+        .map(|line| Message::from(serde_json::from_str::<RawMessage>(line).unwrap()))
+        .collect();
+        assert_eq!(
+            conversation_size_line(&msgs, false),
+            "Messages: 2  ·  ~6 words (~1 min read)"
+        );
     }
-}
 
-fn init_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>> {
-    let mut out = stdout();
-    out.execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
+    #[test]
+    fn legacy_session_ids_match_by_exact_file_stem() {
+        let uuid = "019c24fb-6f78-7a20-99d0-88871c381f5d";
+        assert_eq!(
+            SessionStore::session_id_from_file_name(Path::new(&format!(
+                "/x/rollout-2026-02-13T17-00-00-{uuid}.jsonl"
+            )))
+            .as_deref(),
+            Some(uuid)
+        );
+        assert_eq!(
+            SessionStore::session_id_or_stem_from_file_name(Path::new("/x/legacy42.jsonl.gz"))
+                .as_deref(),
+            Some("legacy42")
+        );
+        // Scans only take UUID-named files as sessions.
+        assert_eq!(
+            SessionStore::session_id_from_file_name(Path::new("/x/legacy42.jsonl.gz")),
+            None
+        );
 
-    let backend = CrosstermBackend::new(out);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
-}
+        let dir = env::temp_dir().join(format!(
+            "cs-rs-legacy-id-test-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(dir.join("nested")).expect("failed to create fixture dir");
+        fs::write(dir.join("nested").join("old-legacy42.jsonl"), "").unwrap();
+        fs::write(dir.join("nested").join("legacy42.jsonl"), "").unwrap();
+        let found = SessionStore::find_file_by_session_id(&dir, "legacy42", 4);
+        assert_eq!(found, Some(dir.join("nested").join("legacy42.jsonl")));
+        assert_eq!(
+            SessionStore::find_file_by_session_id(&dir, "legacy", 4),
+            None
+        );
 
-fn cleanup_terminal<B: std::io::Write>(terminal: &mut Terminal<CrosstermBackend<B>>) -> Result<()> {
-    disable_raw_mode()?;
-    terminal.backend_mut().execute(LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
-    Ok(())
-}
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn shell_single_quote(value: &str) -> String {
-    let escaped = value.replace('"', "\\\"");
-    format!("\"{escaped}\"")
-}
+    #[test]
+    fn compact_list_omits_header_and_separator() {
+        let session = SessionInfo {
+            display: "compact title".to_string(),
+            project: "/tmp/compact".to_string(),
+            ..test_session("compact-session", SessionSource::Claudecode, 1)
+        };
+        let full = list_sessions(
+            vec![session.clone()],
+            ListFormat::Table,
+            10,
+            &[],
+            TableOptions::default(),
+            &HashMap::new(),
+        );
+        assert_eq!(full.lines().count(), 3);
+        assert!(full.starts_with("#  source"));
+        assert!(full.lines().nth(2).unwrap().starts_with("1  "));
 
-fn resolve_resume_cwd(session: &SessionInfo) -> Result<PathBuf> {
-    let configured = Path::new(&session.project);
-    if configured.as_os_str().is_empty() {
-        return Err(anyhow!("Session project path is empty"));
+        let table = TableOptions {
+            compact: true,
+            ..TableOptions::default()
+        };
+        let compact = list_sessions(
+            vec![session],
+            ListFormat::Table,
+            10,
+            &[],
+            table,
+            &HashMap::new(),
+        );
+        assert_eq!(compact.lines().count(), 1);
+        assert!(compact.ends_with("compact title\n"));
     }
 
-    if !configured.exists() {
-        fs::create_dir_all(configured).with_context(|| {
-            format!(
-                "failed to create project directory {}",
-                configured.display()
+    #[test]
+    fn search_results_show_model_and_effort() {
+        let session = SessionInfo {
+            display: "fix the parser".to_string(),
+            project: "/tmp/search".to_string(),
+            model: "gpt-5.3-codex".to_string(),
+            reasoning_effort: "high".to_string(),
+            ..test_session(
+                "019c24fb-6f78-7a20-99d0-88871c381f5d",
+                SessionSource::Codex,
+                1,
             )
-        })?;
+        };
+        let msg = Message::from(
+            serde_json::from_str::<RawMessage>(
+                r#"{"type":"assistant","message":{"role":"assistant","model":"gpt-5","content":"parser fixed"}}"#,
+            )
+            .unwrap(),
+        );
+        let out = render_search_results(vec![(session, msg, "parser fixed".to_string())]);
+        let header = out.lines().nth(2).unwrap();
+        assert!(header.ends_with("/tmp/search  gpt-5 (high)"), "{header}");
     }
 
-    if configured.exists() {
-        return Ok(configured.to_path_buf());
+    #[test]
+    fn zero_limit_lists_everything() {
+        let sessions: Vec<SessionInfo> = (0..3)
+            .map(|idx| SessionInfo {
+                display: format!("session {idx}"),
+                project: "/tmp/limit".to_string(),
+                ..test_session(&format!("limit-{idx}"), SessionSource::Claudecode, 1)
+            })
+            .collect();
+        let mut out = Vec::new();
+        write_sessions_json_lines(&sessions, 0, &[], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+        let compact = TableOptions {
+            compact: true,
+            ..TableOptions::default()
+        };
+        let table = list_sessions(
+            sessions,
+            ListFormat::Table,
+            0,
+            &[],
+            compact,
+            &HashMap::new(),
+        );
+        assert_eq!(table.lines().count(), 3);
     }
 
-    Err(anyhow!(
-        "Failed to create or resolve session project directory: {}",
-        configured.display()
-    ))
-}
+    #[test]
+    fn search_within_one_session_reports_every_match() {
+        let path = env::temp_dir().join(format!(
+            "cs-rs-search-session-test-{}-{}.jsonl",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::write(
+            &path,
+            "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"where is the parser\"}}\n\
+{\"type\":\"assistant\",\"message\":{\"role\":\"assistant\",\"content\":[{\"type\":\"text\",\"text\":\"the parser is in src\"}]}}\n\
+{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"thanks\"}}\n",
+        )
+        .expect("failed to write fixture file");
+        let session = SessionInfo {
+            display: "parser".to_string(),
+            project: "/tmp/search".to_string(),
+            file_path: Some(path.to_string_lossy().to_string()),
+            ..test_session("search-session", SessionSource::Claudecode, 1)
+        };
 
-fn resume_session(session: &SessionInfo) -> Result<()> {
-    let session_id = shell_single_quote(&session.session_id);
-    let resume_cmd = session.source.resume_command();
-    let fallback = session.source.fallback_resume_command();
-    let resume_invocation = session.source.resume_invocation();
-    // Claude Code: skip --model so the user's account default (including context
-    // window tier) takes effect. Codex: pass --model to preserve the exact model.
-    let model_arg = if session.source == SessionSource::Codex {
-        if let Some(model) = codex_model_candidate(&session.model) {
+        let mut store = test_store();
+        let options = SearchOptions {
+            session: Some(session),
+            ..SearchOptions::default()
+        };
+        let results = store.search("parser", &options).unwrap();
+        let lines: Vec<&str> = results.iter().map(|(_, _, line)| line.as_str()).collect();
+        assert_eq!(lines, ["where is the parser", "the parser is in src"]);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn turn_time_formats_message_timestamps() {
+        let msg = Message::from(
+            serde_json::from_str::<RawMessage>(
+                r#"{"type":"user","timestamp":"2026-02-13T17:00:05Z","message":{"role":"user","content":"hi"}}"#,
+            )
+            .unwrap(),
+        );
+        let expected = display_time(1_771_002_005_000)
+            .unwrap()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        assert_eq!(turn_time(&msg), Some(expected));
+    }
+
+    #[test]
+    fn project_alias_prefers_most_specific_path() {
+        let mut config = Config::default();
+        config
+            .project_aliases
+            .insert("/srv/work/acme-backend".to_string(), "acme".to_string());
+        config.project_aliases.insert(
+            "/srv/work/acme-backend/api/".to_string(),
+            "acme-api".to_string(),
+        );
+        assert_eq!(
+            config.project_alias("/srv/work/acme-backend").as_deref(),
+            Some("acme")
+        );
+        assert_eq!(
+            config
+                .project_alias("/srv/work/acme-backend/web")
+                .as_deref(),
+            Some("acme/web")
+        );
+        assert_eq!(
+            config
+                .project_alias("/srv/work/acme-backend/api/v2")
+                .as_deref(),
+            Some("acme-api/v2")
+        );
+        assert_eq!(config.project_alias("/srv/work/acme-backend-old"), None);
+    }
+
+    #[test]
+    fn codex_file_info_isolates_segments_in_multi_session_files() {
+        let first = "019c24fb-6f78-7a20-99d0-88871c381f60";
+        let second = "019c24fb-6f78-7a20-99d0-88871c381f61";
+        let path = env::temp_dir().join(format!(
+            "cs-rs-codex-multi-test-{}-{}.jsonl",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let meta = |id: &str, ts: &str, cwd: &str| {
             format!(
-                " {} {}",
-                session.source.resume_model_flag(),
-                shell_single_quote(&model)
+                "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{id}\",\"timestamp\":\"{ts}\",\"cwd\":\"{cwd}\"}}}}\n"
             )
-        } else {
-            String::new()
-        }
-    } else {
-        String::new()
-    };
-    let effort_arg = if session.source == SessionSource::Codex {
-        codex_effort_candidate(&session.reasoning_effort)
-            .map(|effort| {
-                let config_pair = format!("model_reasoning_effort=\"{effort}\"");
-                format!(" -c {}", shell_single_quote(&config_pair))
-            })
-            .unwrap_or_default()
-    } else {
-        String::new()
-    };
-    let script = format!(
-        "cs_session_id={session_id}; if whence -w {resume_cmd} >/dev/null 2>&1; then {resume_cmd} {invocation}{model_arg}{effort_arg}; elif whence -w {fallback} >/dev/null 2>&1; then {fallback} {invocation}{model_arg}{effort_arg}; fi",
-        session_id = session_id,
-        resume_cmd = resume_cmd,
-        invocation = resume_invocation,
-        fallback = fallback,
-        model_arg = model_arg,
-        effort_arg = effort_arg,
-    );
+        };
+        let turn = |model: &str, effort: &str| {
+            format!(
+                "{{\"type\":\"turn_context\",\"payload\":{{\"model\":\"{model}\",\"effort\":\"{effort}\"}}}}\n"
+            )
+        };
+        let user = |text: &str| {
+            format!(
+                "{{\"type\":\"response_item\",\"payload\":{{\"type\":\"message\",\"role\":\"user\",\"content\":[{{\"type\":\"input_text\",\"text\":\"{text}\"}}]}}}}\n"
+            )
+        };
+        let fixture = [
+            meta(first, "2026-02-13T17:00:00.000Z", "/tmp/first"),
+            turn("gpt-5.2-codex", "low"),
+            user("first prompt"),
+            meta(second, "2026-02-13T18:00:00.000Z", "/tmp/second"),
+            turn("gpt-5.3-codex", "high"),
+            user("second prompt"),
+            // The first session resumed later in the same file.
+            meta(first, "2026-02-13T19:00:00.000Z", "/tmp/first"),
+            turn("gpt-5.2-codex", "medium"),
+        ]
+        .concat();
+        fs::write(&path, fixture).expect("failed to write fixture file");
+
+        let store = test_store();
+        let info = store
+            .codex_file_info_from_session_file(&path, second)
+            .expect("expected info for the second session");
+        assert_eq!(info.cwd.as_deref(), Some("/tmp/second"));
+        assert_eq!(info.model.as_deref(), Some("gpt-5.3-codex"));
+        assert_eq!(info.reasoning_effort.as_deref(), Some("high"));
+        assert_eq!(info.timestamp_ms, Some(1_771_005_600_000));
+        assert_eq!(info.title.as_deref(), Some("second prompt"));
+
+        let info = store
+            .codex_file_info_from_session_file(&path, first)
+            .expect("expected info for the first session");
+        assert_eq!(info.cwd.as_deref(), Some("/tmp/first"));
+        assert_eq!(info.model.as_deref(), Some("gpt-5.2-codex"));
+        assert_eq!(info.reasoning_effort.as_deref(), Some("medium"));
+        assert_eq!(info.timestamp_ms, Some(1_771_002_000_000));
+        assert_eq!(info.title.as_deref(), Some("first prompt"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn sort_sessions_orders_by_key_then_recency() {
+        let session = |id: &str, ts: i64, project: &str, model: &str| SessionInfo {
+            display: id.to_string(),
+            project: project.to_string(),
+            model: model.to_string(),
+            ..test_session(id, SessionSource::Claudecode, ts)
+        };
+        let mut sessions = vec![
+            session("a", 1, "/b", "opus"),
+            session("b", 3, "/a", "sonnet"),
+            session("c", 2, "/b", "haiku"),
+        ];
+        let times = build_list_time_ms_map(&sessions);
+        let ids = |sessions: &[SessionInfo]| {
+            sessions
+                .iter()
+                .map(|s| s.session_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let resumed: HashMap<String, i64> = [("a", 10), ("c", 20)]
+            .into_iter()
+            .map(|(id, ms)| (SessionSource::Claudecode.internal_key(id), ms))
+            .collect();
+
+        sort_sessions(&mut sessions, SessionSort::Recent, &times, &resumed);
+        assert_eq!(ids(&sessions), ["b", "c", "a"]);
+        sort_sessions(&mut sessions, SessionSort::Oldest, &times, &resumed);
+        assert_eq!(ids(&sessions), ["a", "c", "b"]);
+        sort_sessions(&mut sessions, SessionSort::Project, &times, &resumed);
+        assert_eq!(ids(&sessions), ["b", "c", "a"]);
+        sort_sessions(&mut sessions, SessionSort::Model, &times, &resumed);
+        assert_eq!(ids(&sessions), ["c", "a", "b"]);
+        sort_sessions(&mut sessions, SessionSort::Resumed, &times, &resumed);
+        assert_eq!(ids(&sessions), ["c", "a", "b"]);
+        assert_eq!(SessionSort::Model.next(), SessionSort::Resumed);
+        assert_eq!(SessionSort::Resumed.next(), SessionSort::Recent);
+    }
+
+    #[test]
+    fn encode_path_matches_claude_project_dir_names() {
+        assert_eq!(SessionStore::encode_path("/tmp/x"), "-tmp-x");
+        assert_eq!(
+            SessionStore::encode_path("/Users/me/my_app.v2"),
+            "-Users-me-my-app-v2"
+        );
+        assert_eq!(
+            SessionStore::encode_path("/home/me/My Project (old)"),
+            "-home-me-My-Project--old-"
+        );
+        assert_eq!(SessionStore::encode_path("/srv/café"), "-srv-caf-");
+        assert_eq!(SessionStore::encode_path("/srv/🦀"), "-srv---");
+    }
+
+    #[test]
+    fn session_counts_group_and_sort_by_frequency() {
+        let session = |id: &str, source, model: &str| SessionInfo {
+            display: id.to_string(),
+            project: "/tmp/counts".to_string(),
+            model: model.to_string(),
+            ..test_session(id, source, 1)
+        };
+        let sessions = vec![
+            session("a", SessionSource::Claudecode, "claude-opus-4-6"),
+            session("b", SessionSource::Codex, "gpt-5"),
+            session("c", SessionSource::Codex, "gpt-5"),
+        ];
+        let mut store = test_store();
+        let out = render_session_counts(&mut store, sessions.clone(), CountBy::Model);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["gpt-5", "2"]
+        );
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            ["claude-opus-4-6", "1"]
+        );
+        assert_eq!(
+            lines[2].split_whitespace().collect::<Vec<_>>(),
+            ["total", "3"]
+        );
 
-    let mut cmd = Command::new("zsh");
-    cmd.arg("-ic").arg(script);
-    let project_path = resolve_resume_cwd(session)?;
-    cmd.current_dir(project_path);
-    let status = cmd
-        .status()
-        .with_context(|| "failed to launch shell resume command")?;
+        let out = render_session_counts(&mut store, sessions, CountBy::Source);
+        assert!(out.starts_with("codex"));
+    }
 
-    if !status.success() {
-        eprintln!("Resume command exited with status: {status}");
+    #[test]
+    fn pinned_sessions_sort_first_in_order() {
+        let session = |id: &str| SessionInfo {
+            display: id.to_string(),
+            project: "/tmp/pins".to_string(),
+            ..test_session(id, SessionSource::Claudecode, 1)
+        };
+        let mut store = test_store();
+        store.cache.pinned = vec![
+            SessionSource::Claudecode.internal_key("c"),
+            SessionSource::Claudecode.internal_key("d"),
+        ];
+        let mut sessions = vec![session("a"), session("d"), session("b"), session("c")];
+        store.pinned_first(&mut sessions);
+        let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["d", "c", "a", "b"]);
     }
-    std::process::exit(status.code().unwrap_or(1));
-}
 
-fn home_dir() -> PathBuf {
-    PathBuf::from(env::var("HOME").unwrap_or_else(|_| String::from("/Users/mbm-gsc")))
-}
+    #[test]
+    fn git_branch_skips_missing_and_non_git_projects() {
+        assert_eq!(git_branch(""), None);
+        assert_eq!(git_branch("/nonexistent/cs-rs-branch-test"), None);
+        let dir = env::temp_dir().join(format!(
+            "cs-rs-branch-test-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(&dir).expect("failed to create fixture dir");
+        assert_eq!(git_branch(&dir.to_string_lossy()), None);
+        let _ = fs::remove_dir_all(dir);
+    }
 
-fn list_command(
-    store: &mut SessionStore,
-    project: Option<String>,
-    since: Option<String>,
-    limit: usize,
-    json: bool,
-) -> Result<String> {
-    let mut sessions = store.all();
+    #[test]
+    fn strip_ansi_removes_escape_sequences() {
+        assert_eq!(
+            strip_ansi("\x1b[1;32mCompiling\x1b[0m crate"),
+            "Compiling crate"
+        );
+        assert_eq!(
+            strip_ansi("\x1b]8;;https://example.com\x07link\x1b]8;;\x07"),
+            "link"
+        );
+        assert_eq!(strip_ansi("\x1b[2K\x1b[1Gdone\x1bM"), "done");
+        assert_eq!(strip_ansi("plain [text]"), "plain [text]");
+    }
 
-    if let Some(p) = project.as_deref() {
-        let p = p.to_lowercase();
-        sessions.retain(|s| s.project.to_lowercase().contains(&p));
+    #[test]
+    fn parallel_map_preserves_input_order() {
+        let items: Vec<u64> = (0..100).collect();
+        let squares = parallel_map(&items, 4, |n| {
+            // Uneven work so workers finish out of order.
+            thread::sleep(StdDuration::from_micros((100 - n) * 10));
+            n * n
+        });
+        assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+        assert_eq!(parallel_map(&items[..1], 4, |n| n + 1), vec![1]);
     }
 
-    if let Some(since_s) = since {
-        let since_ms = chrono::NaiveDate::parse_from_str(&since_s, "%Y-%m-%d")
-            .map(|date| {
-                date.and_hms_opt(0, 0, 0)
-                    .and_then(|naive| Local.from_local_datetime(&naive).single())
-                    .map(|ts| ts.timestamp_millis())
-            })
-            .ok()
-            .flatten()
-            .with_context(|| format!("Invalid date format: {since_s} (use YYYY-MM-DD)"))?;
+    #[test]
+    fn tool_use_summary_keeps_full_command_when_asked() {
+        let command = format!("cargo test {}", "--package crate ".repeat(10));
+        let input = json!({ "command": command });
+        let short = tool_use_summary("Bash", &input, false);
+        assert!(short.ends_with('…'));
+        assert_eq!(short.chars().count(), 2 + 80);
+        assert_eq!(
+            tool_use_summary("Bash", &input, true),
+            format!("$ {}", command.trim())
+        );
+    }
 
-        sessions.retain(|s| s.timestamp >= since_ms);
+    #[test]
+    fn list_row_arg_accepts_only_short_row_numbers() {
+        assert_eq!(list_row_arg("3"), Some(3));
+        assert_eq!(list_row_arg("120"), Some(120));
+        assert_eq!(list_row_arg("0"), None);
+        assert_eq!(list_row_arg("1234"), None);
+        assert_eq!(list_row_arg("3a"), None);
+        assert_eq!(list_row_arg(""), None);
     }
 
-    sessions.sort_by_cached_key(|s| Reverse(list_time_ms_for_session(s)));
-    Ok(list_sessions(sessions, json, limit))
-}
+    #[test]
+    fn list_filters_describe_names_active_filters() {
+        assert_eq!(ListFilters::default().describe(), None);
+        let filters = ListFilters {
+            project: Some(ProjectFilter::Pattern("/tmp/my app".to_string())),
+            since: Some("2026-01-01".to_string()),
+            min_messages: Some(5),
+            ..ListFilters::default()
+        };
+        assert_eq!(
+            filters.describe().as_deref(),
+            Some("project=\"/tmp/my app\" since=2026-01-01 min-messages=5")
+        );
+    }
 
-#[derive(Parser)]
-#[command(name = "cs-rs", about = "Session tools for Claude Code and Codex")]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<Commands>,
-}
+    #[test]
+    fn sessions_in_archived_sessions_dir_are_archived() {
+        let source = SessionSource::Codex;
+        let mut session = test_session("archived-check", source, 0);
+        assert!(!session.is_archived());
+        session.file_path = Some(
+            source
+                .archived_sessions_dir()
+                .join("rollout-archived-check.jsonl")
+                .to_string_lossy()
+                .to_string(),
+        );
+        assert!(session.is_archived());
+        session.file_path = Some(
+            source
+                .home_base()
+                .join("sessions")
+                .join("rollout-archived-check.jsonl")
+                .to_string_lossy()
+                .to_string(),
+        );
+        assert!(!session.is_archived());
+    }
 
-#[derive(Subcommand)]
-enum Commands {
-    View {
-        session_id: String,
-        #[arg(long)]
-        thinking: bool,
-        #[arg(short, long)]
-        tail: Option<usize>,
-        #[arg(long)]
-        no_pager: bool,
-    },
-    Search {
-        query: String,
-        #[arg(short, long)]
-        project: Option<String>,
-        #[arg(short, long, default_value_t = 50)]
-        max: usize,
-    },
-    Stats,
-    List {
-        #[arg(short, long)]
-        project: Option<String>,
-        #[arg(short, long)]
-        since: Option<String>,
-        #[arg(short, long, default_value_t = 50)]
-        limit: usize,
-        #[arg(long)]
-        json: bool,
-    },
-}
+    #[test]
+    fn centered_rect_centers_and_clamps() {
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(centered_rect(40, 10, area), Rect::new(20, 7, 40, 10));
+        assert_eq!(centered_rect(100, 30, area), area);
+        let offset = Rect::new(10, 5, 20, 10);
+        assert_eq!(centered_rect(10, 4, offset), Rect::new(15, 8, 10, 4));
+    }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let mut store = SessionStore::new();
+    #[test]
+    fn conversation_duration_spans_first_to_last_message() {
+        let msgs: Vec<Message> = [
+            "2026-02-13T17:00:00.000Z",
+            "2026-02-13T20:05:30.000Z",
+            "2026-02-13T17:10:00.000Z",
+        ]
+        .iter()
+        .map(|ts| {
+            Message::from(
+                serde_json::from_str::<RawMessage>(&format!(
+                    r#"{{"type":"user","timestamp":"{ts}","message":{{"role":"user","content":"hi"}}}}"#
+                ))
+                .unwrap(),
+            )
+        })
+        .collect();
+        let duration = conversation_duration_ms(&msgs).unwrap();
+        assert_eq!(format_duration(duration), "3h05m");
+        assert_eq!(conversation_duration_ms(&[]), None);
+        assert_eq!(conversation_duration_ms(&msgs[..1]), None);
+        assert_eq!(format_duration(20_000), "<1m");
+        assert_eq!(format_duration(42 * 60_000), "42m");
+        assert_eq!(format_duration((2 * 1440 + 4 * 60) * 60_000), "2d4h");
+    }
 
-    match cli.command {
-        None => {
-            run_tui()?;
-        }
-        Some(Commands::View {
-            session_id,
-            thinking,
-            tail,
-            no_pager,
-        }) => {
-            let session = store
-                .get(&session_id)
-                .with_context(|| format!("Session not found: {session_id}"))?;
-            let lines = render_conversation(&store, &session, thinking, tail);
-            output_with_optional_pager(&lines.join("\n"), no_pager)?;
-        }
-        Some(Commands::Search {
-            query,
-            project,
-            max,
-        }) => {
-            let results = store.search(&query, project.as_deref(), max)?;
-            println!("{}", render_search_results(results));
-        }
-        Some(Commands::Stats) => {
-            let stats = store.build_stats_report();
-            println!("{}", render_stats(&stats));
-        }
-        Some(Commands::List {
-            project,
-            since,
-            limit,
-            json,
-        }) => {
-            let output = list_command(&mut store, project, since, limit, json)?;
-            println!("{}", output);
+    #[test]
+    fn export_sessions_writes_one_file_per_session_by_project() {
+        let root = env::temp_dir().join(format!("cs-rs-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let transcript = root.join("export-1.jsonl");
+        fs::write(
+            &transcript,
+            "{\"type\":\"user\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"message\":{\"role\":\"user\",\"content\":\"export me\"}}\n",
+        )
+        .unwrap();
+        let session = |id: &str, file_path: Option<String>| SessionInfo {
+            display: "export".to_string(),
+            project: "/tmp/my_app".to_string(),
+            file_path,
+            ..test_session(id, SessionSource::Claudecode, 1)
+        };
+        let empty = root.join("empty.jsonl");
+        fs::write(&empty, "").unwrap();
+        let sessions = vec![
+            session("export-1", Some(transcript.to_string_lossy().to_string())),
+            session("export-2", None),
+            session("export-3", Some(empty.to_string_lossy().to_string())),
+            session(
+                "export-4",
+                Some(root.join("missing.jsonl").to_string_lossy().to_string()),
+            ),
+            session(
+                "../../escape",
+                Some(transcript.to_string_lossy().to_string()),
+            ),
+        ];
+
+        let store = test_store();
+        let out = root.join("out");
+        assert_eq!(
+            export_sessions(&store, &sessions, &out, ExportFormat::Md),
+            (2, 3)
+        );
+        let project_dir = out.join("-tmp-my-app");
+        let written = fs::read_to_string(project_dir.join("export-1.md")).unwrap();
+        assert!(written.contains("You: export me"), "{written}");
+        assert!(project_dir.join("______escape.md").is_file());
+        assert!(!root.join("escape.md").exists());
+        let mut names: Vec<String> = fs::read_dir(&project_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["______escape.md", "export-1.md"]);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn fuzzy_project_matches_name_subsequence() {
+        assert!(project_matches(
+            "/Users/me/w/acme-backend",
+            "Backend",
+            false
+        ));
+        assert!(!project_matches("/Users/me/w/acme-backend", "acbk", false));
+        assert!(project_matches("/Users/me/w/acme-backend", "acbk", true));
+        assert!(project_matches("/Users/me/w/acme-backend/", "ACBK", true));
+        // Only the directory name is fuzzy-matched, not the whole path.
+        assert!(!project_matches("/Users/me/w/acme-backend", "usme", true));
+        assert!(!project_matches("/Users/me/w/acme-backend", "bkac", true));
+    }
+
+    #[test]
+    fn relative_projects_are_never_resolved_or_created() {
+        let session = SessionInfo {
+            display: "relative".to_string(),
+            project: "cs-rs-relative-project".to_string(),
+            ..test_session("relative-session", SessionSource::Claudecode, 1)
+        };
+        assert_eq!(absolute_project_path(&session.project), None);
+        assert_eq!(
+            absolute_project_path("~/work/app"),
+            Some(home_dir().join("work/app"))
+        );
+        assert_eq!(short_project(&session.project), "./cs-rs-relative-project");
+
+        let create = ResumeOptions {
+            create_cwd: true,
+            ..ResumeOptions::default()
+        };
+        // Tests run without a terminal on stdin, so this refuses instead of prompting.
+        if !io::stdin().is_terminal() {
+            let err = resolve_resume_cwd(&session, &create).unwrap_err();
+            assert!(err.to_string().contains("is relative"), "{err}");
         }
+        assert!(!Path::new(&session.project).exists());
     }
 
-    Ok(())
-}
+    #[test]
+    fn transcripts_outside_source_dirs_are_parsed_by_content() {
+        let dir = env::temp_dir().join(format!("cs-rs-sniff-{}/.codex", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let claude = dir.join("claude-copy.jsonl");
+        fs::write(
+            &claude,
+            "{\"type\":\"summary\",\"summary\":\"copied\"}\n\
+{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"still claude\"}}\n",
+        )
+        .unwrap();
+        let empty = dir.join("empty.jsonl");
+        fs::write(&empty, "\n").unwrap();
 
-fn output_with_optional_pager(output: &str, no_pager: bool) -> Result<()> {
-    if no_pager || !io::stdout().is_terminal() {
-        println!("{output}");
-        return Ok(());
+        assert_eq!(
+            sniff_transcript_source(&claude),
+            Some(SessionSource::Claudecode)
+        );
+        assert_eq!(sniff_transcript_source(&empty), None);
+        // Content beats the `.codex` path component.
+        assert_eq!(
+            SessionStore::source_for_file(&claude),
+            SessionSource::Claudecode
+        );
+        assert_eq!(SessionStore::source_for_file(&empty), SessionSource::Codex);
+
+        // A session labeled Codex whose file is really Claude still reads.
+        let session = SessionInfo {
+            file_path: Some(claude.to_string_lossy().to_string()),
+            ..test_session("claude-copy", SessionSource::Codex, 0)
+        };
+        let msgs = test_store().read_messages(&session, true);
+        let user: Vec<String> = msgs
+            .iter()
+            .filter(|msg| msg.msg_type == "user")
+            .map(Message::text)
+            .collect();
+        assert_eq!(user, ["still claude"]);
+
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
     }
 
-    let mut proc = match Command::new("less")
-        .arg("-R")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::inherit())
-        .spawn()
-    {
-        Ok(proc) => proc,
-        Err(_) => {
-            println!("{output}");
-            return Ok(());
-        }
-    };
+    #[test]
+    fn load_skips_empty_and_whitespace_only_session_files() {
+        let root = env::temp_dir().join(format!(
+            "cs-rs-blank-test-{}-{}",
+            std::process::id(),
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let claude = root.join(".claude");
+        let project_dir = claude.join("projects").join("-tmp-blank");
+        fs::create_dir_all(&project_dir).expect("failed to create fixture dir");
+        let history: String = ["kept", "empty", "blank"]
+            .iter()
+            .map(|id| {
+                format!("{{\"sessionId\":\"{id}\",\"display\":\"{id}\",\"timestamp\":1771002000000,\"project\":\"/tmp/blank\"}}\n")
+            })
+            .collect();
+        fs::write(claude.join("history.jsonl"), history).expect("failed to write history");
+        fs::write(
+            project_dir.join("kept.jsonl"),
+            "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"kept\"}}\n",
+        )
+        .expect("failed to write transcript");
+        fs::write(project_dir.join("empty.jsonl"), "").expect("failed to write transcript");
+        fs::write(project_dir.join("blank.jsonl"), "\n  \n\t\n")
+            .expect("failed to write transcript");
 
-    if let Some(mut stdin) = proc.stdin.take() {
-        if let Err(err) = stdin.write_all(output.as_bytes()) {
-            let _ = err;
-            println!("{output}");
-            return Ok(());
-        }
+        let _env = DATA_DIR_ENV.lock().unwrap_or_else(|err| err.into_inner());
+        env::set_var("CS_RS_DATA_DIR", &root);
+        let mut store = SessionStore::new();
+        let sessions = store.all();
+        env::remove_var("CS_RS_DATA_DIR");
+
+        let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["kept"]);
+
+        let _ = fs::remove_dir_all(root);
     }
-    proc.wait().with_context(|| "pager exited with error")?;
 
-    Ok(())
-}
+    #[test]
+    fn wrap_text_breaks_at_words_and_keeps_indent() {
+        assert_eq!(wrap_text("short line", 20), "short line");
+        assert_eq!(
+            wrap_text("You: the quick brown fox jumps", 12),
+            "You: the\nquick brown\nfox jumps"
+        );
+        assert_eq!(
+            wrap_text("    ↳ first second third", 14),
+            "    ↳ first\n    second\n    third"
+        );
+        assert_eq!(wrap_text("abcdefghij", 4), "abcd\nefgh\nij");
+        assert_eq!(wrap_text("日本語テキスト", 6), "日本語\nテキス\nト");
+        assert_eq!(wrap_text("a\n\nb", 5), "a\n\nb");
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn resume_shell_modes_map_to_zsh_flags() {
+        assert_eq!(resume_shell_flag("interactive"), Some("-ic"));
+        assert_eq!(resume_shell_flag("Login"), Some("-lc"));
+        assert_eq!(resume_shell_flag("plain"), Some("-c"));
+        assert_eq!(resume_shell_flag("-lc"), Some("-lc"));
+        assert_eq!(resume_shell_flag("bash"), None);
+        assert_eq!(Config::default().resume_shell_flag(), "-ic");
+        let login = Config {
+            resume_shell: Some("login".into()),
+            ..Config::default()
+        };
+        assert_eq!(login.resume_shell_flag(), "-lc");
+    }
 
-    fn test_store() -> SessionStore {
-        SessionStore {
-            sessions: HashMap::new(),
-            loaded: true,
-            cache: SessionCache {
-                version: 1,
-                ..SessionCache::default()
-            },
-            cache_dirty: false,
-            search_text_cache: HashMap::new(),
-        }
+    #[test]
+    fn doctor_report_shows_paths_cache_and_counts() {
+        let report = DoctorReport {
+            home: PathBuf::from("/home/me"),
+            data_home: PathBuf::from("/home/me"),
+            cache_path: PathBuf::from("/home/me/.local/state/cs-rs/session-cache-v1.json"),
+            cache_status: "readable".to_string(),
+            corrupt_backup: None,
+            ignore_path: PathBuf::from("/home/me/.config/cs-rs/ignore"),
+            sources: vec![SourceDiagnostics {
+                source: SessionSource::Codex,
+                paths: vec![
+                    (
+                        "history",
+                        PathBuf::from("/home/me/.codex/history.jsonl"),
+                        true,
+                    ),
+                    ("sessions", PathBuf::from("/home/me/.codex/sessions"), false),
+                ],
+                counts: LoadCounts {
+                    in_history: 5,
+                    missing_file: 2,
+                    ignored: 0,
+                },
+                loaded: 3,
+            }],
+        };
+        let text = render_doctor_report(&report);
+        assert!(text.starts_with("Home:       /home/me\n"));
+        assert!(!text.contains("Data home"));
+        assert!(text.contains("session-cache-v1.json  (readable)"));
+        assert!(text.contains("\ncodex\n  history   ok       /home/me/.codex/history.jsonl\n"));
+        assert!(text.contains("  sessions  missing  /home/me/.codex/sessions\n"));
+        assert!(
+            text.ends_with("3 session(s) loaded of 5 in history; 2 without a session file on disk")
+        );
     }
 
     #[test]
-    fn get_exact_falls_back_to_recent_source_model() {
-        let source = SessionSource::Claudecode;
-        let recent = SessionInfo {
-            source,
-            session_id: "recent-session".to_string(),
-            display: "recent".to_string(),
+    fn most_recent_model_for_source_uses_newest_session_with_a_model() {
+        let session = |id: &str, timestamp: i64, model: &str, effort: &str| SessionInfo {
+            display: id.to_string(),
             project: "/tmp/project".to_string(),
-            timestamp: 2_000,
-            model: "claude-opus-4-6".to_string(),
-            reasoning_effort: String::new(),
-            file_path: Some("/tmp/recent.jsonl".to_string()),
+            model: model.to_string(),
+            reasoning_effort: effort.to_string(),
+            ..test_session(id, SessionSource::Codex, timestamp)
         };
-        let target = SessionInfo {
-            source,
-            session_id: "target-session".to_string(),
-            display: "target".to_string(),
-            project: "/tmp/project".to_string(),
-            timestamp: 1_000,
-            model: String::new(),
-            reasoning_effort: String::new(),
-            file_path: Some("/tmp/target.jsonl".to_string()),
+        let mut store = test_store();
+        for info in [
+            session("old", 1_000, "gpt-5", "low"),
+            session("newer", 2_000, "gpt-5.1-codex", "high"),
+            session("newest-no-model", 3_000, "", ""),
+        ] {
+            store
+                .sessions
+                .insert(info.source.internal_key(&info.session_id), info);
+        }
+        assert_eq!(
+            store.most_recent_model_for_source(SessionSource::Codex, None),
+            Some(("gpt-5.1-codex".to_string(), "high".to_string()))
+        );
+        assert_eq!(
+            store.most_recent_model_for_source(SessionSource::Codex, Some("newer")),
+            Some(("gpt-5".to_string(), "low".to_string()))
+        );
+        assert_eq!(
+            store.most_recent_model_for_source(SessionSource::Claudecode, None),
+            None
+        );
+    }
+
+    #[test]
+    fn ignore_rules_match_project_globs_and_id_prefixes() {
+        let rules =
+            IgnoreRules::parse("# scratch work\n/tmp/scratch\n/work/*-sandbox/\n\n0199ab\n");
+        let session = |id: &str, project: &str| SessionInfo {
+            project: project.to_string(),
+            ..test_session(id, SessionSource::Claudecode, 0)
         };
+        assert!(rules.matches(&session("a", "/tmp/scratch")));
+        assert!(rules.matches(&session("a", "/tmp/scratch/deep/dir")));
+        assert!(!rules.matches(&session("a", "/tmp/scratchpad")));
+        assert!(rules.matches(&session("a", "/work/api-sandbox")));
+        assert!(!rules.matches(&session("a", "/work/api")));
+        assert!(rules.matches(&session("0199abcd-1", "/work/api")));
+        assert!(IgnoreRules::parse("# nothing\n").is_empty());
+    }
 
+    #[test]
+    fn filter_scan_appends_matches_in_order_across_steps() {
         let mut store = test_store();
-        store
-            .sessions
-            .insert(source.internal_key(&recent.session_id), recent);
-        store
-            .sessions
-            .insert(source.internal_key(&target.session_id), target);
-
-        let resolved = store
-            .get_exact(source, "target-session")
-            .expect("target session should resolve");
-        assert_eq!(resolved.model, "claude-opus-4-6");
+        let session = |id: &str, display: &str| SessionInfo {
+            display: display.to_string(),
+            project: "/tmp/p".to_string(),
+            ..test_session(id, SessionSource::Claudecode, 1)
+        };
+        let sessions = vec![
+            session("a", "fix parser"),
+            session("b", "write docs"),
+            session("c", "parser tests"),
+        ];
+        let mut filtered = sessions.clone();
+        let mut previous = String::new();
+        let mut scan = FilterScan::start(
+            &mut filtered,
+            &sessions,
+            &mut previous,
+            "Parser",
+            false,
+            None,
+        );
+        assert!(filtered.is_empty());
+        let mut steps = 0;
+        while !scan.step(&mut store, &mut filtered, StdDuration::ZERO) {
+            steps += 1;
+        }
+        assert_eq!(steps, 2, "one session per step with no budget");
+        let ids: Vec<_> = filtered.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+
+        // Narrowing a finished query only rescans its matches.
+        previous = "Parser".to_string();
+        let scan = FilterScan::start(
+            &mut filtered,
+            &sessions,
+            &mut previous,
+            "Parser t",
+            false,
+            None,
+        );
+        assert_eq!(scan.pool.len(), 2);
+        assert!(previous.is_empty());
     }
 
     #[test]
-    fn render_stats_outputs_separate_source_sections() {
-        let report = StatsReport {
-            total_sessions: 2,
-            total_history_entries: 3,
-            last_computed_date: "2026-02-13".to_string(),
-            sources: vec![
-                StatsSourceRow {
-                    source: SessionSource::Claudecode,
-                    sessions: 1,
-                    history_entries: 2,
-                    first_session_date: "2026-02-01".to_string(),
-                    top_models: vec![("claude-opus-4-6".to_string(), 1)],
-                    daily_sessions: vec![("2026-02-13".to_string(), 1)],
-                },
-                StatsSourceRow {
-                    source: SessionSource::Codex,
-                    sessions: 1,
-                    history_entries: 1,
-                    first_session_date: "2026-02-02".to_string(),
-                    top_models: vec![("gpt-5.2-codex".to_string(), 1)],
-                    daily_sessions: vec![("2026-02-13".to_string(), 1)],
-                },
-            ],
+    fn list_json_is_versioned_object() {
+        let session = SessionInfo {
+            display: "hello".to_string(),
+            project: "/tmp/p".to_string(),
+            model: "gpt-5".to_string(),
+            ..test_session("s1", SessionSource::Codex, 1_000)
         };
+        let out = list_sessions(
+            vec![session],
+            ListFormat::Json,
+            10,
+            &["session_id".to_string()],
+            TableOptions::default(),
+            &HashMap::new(),
+        );
+        let value: Value = serde_json::from_str(&out).expect("valid JSON");
+        assert_eq!(value["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(value["sessions"], json!([{ "session_id": "s1" }]));
+    }
 
-        let rendered = render_stats(&report);
-        assert!(rendered.contains("CLAUDE CODE:"));
-        assert!(rendered.contains("CODEX:"));
-        assert!(rendered.contains("claude-opus-4-6"));
-        assert!(rendered.contains("gpt-5.2-codex"));
+    #[test]
+    fn reveal_command_targets_the_session_file() {
+        let path = Path::new("/tmp/proj/abc.jsonl");
+        let (program, args) = reveal_command(path);
+        if cfg!(target_os = "macos") {
+            assert_eq!(program, "open");
+            assert_eq!(args, [OsString::from("-R"), OsString::from(path)]);
+        } else if !cfg!(windows) {
+            assert_eq!(program, "xdg-open");
+            assert_eq!(args, [OsString::from("/tmp/proj")]);
+        }
+
+        let missing = SessionInfo {
+            project: "/tmp/proj".to_string(),
+            ..test_session("gone", SessionSource::Claudecode, 0)
+        };
+        let err = reveal_session_file(&missing).unwrap_err();
+        assert!(err.to_string().contains("No session file found for gone"));
     }
 
     #[test]
-    fn codex_model_candidate_normalizes_effort_suffix() {
+    fn collapse_tools_folds_runs_across_turns() {
+        let dir = env::temp_dir().join(format!("cs-rs-collapse-tools-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        let path = dir.join("collapse.jsonl");
+        let tool_turn = |id: &str, name: &str| {
+            format!(
+                "{{\"type\":\"assistant\",\"uuid\":\"{id}\",\"timestamp\":\"2026-02-13T17:00:01.000Z\",\"message\":{{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{{\"type\":\"tool_use\",\"id\":\"t{id}\",\"name\":\"{name}\",\"input\":{{}}}}]}}}}\n"
+            )
+        };
+        let fixture = [
+            "{\"type\":\"user\",\"uuid\":\"u1\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"message\":{\"role\":\"user\",\"content\":\"fix it\"}}\n".to_string(),
+            tool_turn("a1", "Read"),
+            tool_turn("a2", "Edit"),
+            tool_turn("a3", "Read"),
+            "{\"type\":\"assistant\",\"uuid\":\"a4\",\"timestamp\":\"2026-02-13T17:00:05.000Z\",\"message\":{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{\"type\":\"text\",\"text\":\"done\"}]}}\n".to_string(),
+            tool_turn("a5", "Bash"),
+        ]
+        .concat();
+        fs::write(&path, fixture).expect("write fixture");
+        let session = SessionInfo {
+            display: "fix it".to_string(),
+            project: "/tmp/p".to_string(),
+            file_path: Some(path.to_string_lossy().to_string()),
+            ..test_session("collapse", SessionSource::Claudecode, 1)
+        };
+        let store = test_store();
+        let options = RenderOptions {
+            reverse: true,
+            collapse_tools: true,
+            ..RenderOptions::default()
+        };
+        let lines = render_conversation(&store, &session, options);
+        let body: Vec<&str> = lines
+            .iter()
+            .skip_while(|line| !line.starts_with("You:"))
+            .filter(|line| !line.is_empty())
+            .map(String::as_str)
+            .collect();
         assert_eq!(
-            codex_model_candidate("gpt-5.3-codex high").as_deref(),
-            Some("gpt-5.3-codex")
+            body[..3],
+            [
+                "You: fix it",
+                "Claude (m): [3 tool calls: Read, Edit]",
+                "Claude (m): done",
+            ]
         );
+        // A lone call after the text renders as usual.
+        assert!(body[3].starts_with("Claude (m): [tool] "), "{body:?}");
+        assert_eq!(body.len(), 4);
+        let _ = fs::remove_dir_all(&dir);
+
+        let names: Vec<String> = ["A", "B", "C", "D", "E", "A"].map(String::from).to_vec();
         assert_eq!(
-            codex_model_candidate("  gpt-5.3-codex   medium ").as_deref(),
-            Some("gpt-5.3-codex")
+            collapsed_tools_summary(&names),
+            "[6 tool calls: A, B, C, D…]"
         );
     }
 
     #[test]
-    fn codex_effort_candidate_normalizes_case() {
-        assert_eq!(codex_effort_candidate("HIGH").as_deref(), Some("high"));
-        assert_eq!(codex_effort_candidate("xhigh").as_deref(), Some("xhigh"));
-        assert_eq!(codex_effort_candidate(""), None);
-        assert_eq!(codex_effort_candidate("high effort"), None);
+    fn rotated_history_files_merge_with_current_history() {
+        let dir = env::temp_dir().join(format!("cs-rs-rotated-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        for name in [
+            "history.jsonl",
+            "history.jsonl.1",
+            "history.jsonl.10",
+            "history.jsonl.2",
+            "history.jsonl.bak",
+            "history.jsonl.1.gz",
+        ] {
+            fs::write(dir.join(name), "").expect("write fixture");
+        }
+        let names: Vec<String> = rotated_history_files_in(&dir)
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            ["history.jsonl.10", "history.jsonl.2", "history.jsonl.1"]
+        );
+        let _ = fs::remove_dir_all(&dir);
+
+        let entry = |id: &str, display: &str, timestamp: i64, model: &str| SessionInfo {
+            display: display.to_string(),
+            project: "/tmp/p".to_string(),
+            model: model.to_string(),
+            ..test_session(id, SessionSource::Codex, timestamp)
+        };
+        let mut seen = HashMap::new();
+        merge_history_session(&mut seen, entry("a", "latest prompt", 2_000, "gpt-5"));
+        merge_history_session(&mut seen, entry("a", "rotated prompt", 1_000, ""));
+        merge_history_session(&mut seen, entry("old", "only rotated", 500, ""));
+        assert_eq!(seen.len(), 2);
+        let a = &seen[&SessionSource::Codex.internal_key("a")];
+        assert_eq!((a.display.as_str(), a.timestamp), ("latest prompt", 2_000));
+        assert_eq!(a.model, "gpt-5");
+        assert!(seen.contains_key(&SessionSource::Codex.internal_key("old")));
     }
 
     #[test]
-    fn codex_file_info_extracts_model_and_effort() {
-        let session_id = "019c24fb-6f78-7a20-99d0-88871c381f5d";
-        let file_name = format!(
-            "cs-rs-codex-info-test-{}-{}.jsonl",
-            std::process::id(),
-            Local::now().timestamp_nanos_opt().unwrap_or_default()
-        );
-        let path = env::temp_dir().join(file_name);
-        let fixture = format!(
-            "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{session_id}\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"cwd\":\"/tmp/demo\"}}}}\n\
-{{\"type\":\"turn_context\",\"payload\":{{\"model\":\"gpt-5.3-codex high\",\"effort\":\"HIGH\",\"collaboration_mode\":{{\"settings\":{{\"reasoning_effort\":\"medium\"}}}}}}}}\n"
+    fn message_breakdown_counts_types_blocks_and_errors() {
+        let msgs: Vec<Message> = [
+            r#"{"type":"user","message":{"role":"user","content":"run the tests"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"thinking","thinking":"hm"},{"type":"tool_use","id":"t1","name":"Bash","input":{}}]}}"#,
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            r#"{"type":"assistant","isApiErrorMessage":true,"message":{"role":"assistant","content":"overloaded"}}"#,
+            r#"{"type":"progress"}"#,
+            r#"{"type":"progress"}"#,
+            r#"{"type":"system","message":{}}"#,
+        ]
+        .iter()
+        .map(|line| Message::from(serde_json::from_str::<RawMessage>(line).unwrap()))
+        .collect();
+        assert_eq!(
+            message_breakdown_line(&msgs),
+            "Breakdown: 2 user  ·  2 assistant  ·  1 tool calls  ·  1 thinking  ·  1 API errors  ·  internal: 2 progress  ·  other: 1 system"
         );
-        fs::write(&path, fixture).expect("failed to write fixture file");
-
-        let store = test_store();
-        let info = store
-            .codex_file_info_from_session_file(&path, session_id)
-            .expect("expected codex file info");
+    }
 
-        assert_eq!(info.model.as_deref(), Some("gpt-5.3-codex"));
-        assert_eq!(info.reasoning_effort.as_deref(), Some("high"));
-        assert_eq!(info.cwd.as_deref(), Some("/tmp/demo"));
-        assert_eq!(info.timestamp_ms, Some(1_771_002_000_000));
+    #[test]
+    fn days_before_saturates_instead_of_overflowing() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 13).unwrap();
+        assert_eq!(
+            days_before(date, 13),
+            NaiveDate::from_ymd_opt(2026, 1, 31).unwrap()
+        );
+        assert_eq!(days_before(date, usize::MAX), NaiveDate::MIN);
+        assert_eq!(days_before(date, 100_000_000_000), NaiveDate::MIN);
+        assert!(Cli::try_parse_from(["cs-rs", "stats", "--days", "100000000000"]).is_err());
+        assert!(Cli::try_parse_from(["cs-rs", "stats", "--days", "365"]).is_ok());
+    }
 
-        let _ = fs::remove_file(path);
+    #[test]
+    fn parse_since_rejects_out_of_range_durations() {
+        assert!(parse_since_ms("7d").is_ok());
+        for huge in [
+            "99999999999999d",
+            "9223372036854775807w",
+            "99999999999999999m",
+        ] {
+            let err = parse_since_ms(huge).unwrap_err();
+            assert!(
+                err.to_string().contains("too far in the past"),
+                "{huge}: {err}"
+            );
+        }
     }
 
     #[test]
-    fn session_id_hex_tail_uses_last_five_hex_chars() {
-        let id = "019c24fb-6f78-7a20-99d0-88871c381f5d";
-        assert_eq!(session_id_hex_tail(id, 5), "81f5d");
+    fn display_zone_rejects_multibyte_offsets() {
+        assert_eq!(DisplayZone::parse("+1é1"), None);
+        assert_eq!(DisplayZone::parse("+é1"), None);
+        assert_eq!(
+            DisplayZone::parse("+0530"),
+            FixedOffset::east_opt(5 * 3600 + 30 * 60).map(DisplayZone::Fixed)
+        );
     }
 
     #[test]
-    fn apply_filter_matches_cc_source_alias() {
-        let mut store = test_store();
-        let sessions = vec![
-            SessionInfo {
-                source: SessionSource::Claudecode,
-                session_id: "a".to_string(),
-                display: "one".to_string(),
-                project: "/tmp/a".to_string(),
-                timestamp: 1,
-                model: String::new(),
-                reasoning_effort: String::new(),
-                file_path: None,
-            },
-            SessionInfo {
-                source: SessionSource::Codex,
-                session_id: "b".to_string(),
-                display: "two".to_string(),
-                project: "/tmp/b".to_string(),
-                timestamp: 1,
-                model: String::new(),
-                reasoning_effort: String::new(),
-                file_path: None,
-            },
-        ];
-        let mut filtered = Vec::new();
-        apply_filter(&mut store, &mut filtered, &sessions, "cc");
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].source, SessionSource::Claudecode);
+    fn shell_single_quote_keeps_values_literal() {
+        assert_eq!(shell_single_quote("plain"), "'plain'");
+        assert_eq!(shell_single_quote("it's"), "'it'\\''s'");
+        let tricky = "$(echo pwned) `id` $HOME \\n it's \"quoted\"";
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_single_quote(tricky)))
+            .output()
+            .expect("run sh");
+        assert_eq!(String::from_utf8_lossy(&output.stdout), tricky);
     }
 
     #[test]
-    fn apply_filter_matches_full_text_in_session_messages() {
+    fn destructive_commands_refuse_row_numbers() {
         let mut store = test_store();
-        let session_id = "fulltext-session";
-        let file_name = format!(
-            "cs-rs-fulltext-filter-test-{}-{}.jsonl",
-            std::process::id(),
-            Local::now().timestamp_nanos_opt().unwrap_or_default()
-        );
-        let path = env::temp_dir().join(file_name);
-        let fixture = format!(
-            "{{\"type\":\"user\",\"uuid\":\"u1\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"isApiErrorMessage\":false,\"sessionId\":\"{session_id}\",\"message\":{{\"role\":\"user\",\"content\":[{{\"type\":\"text\",\"text\":\"this includes flibbertigibbet\"}}]}}}}\n"
-        );
-        fs::write(&path, fixture).expect("failed to write fixture file");
+        let session = SessionInfo {
+            display: "x".to_string(),
+            project: "/tmp/p".to_string(),
+            ..test_session("3f2a9c1e-row", SessionSource::Claudecode, 1)
+        };
+        store
+            .sessions
+            .insert(session.source.internal_key(&session.session_id), session);
+        let err = resolve_destructive_arg(&mut store, "3", "delete").unwrap_err();
+        assert!(err.to_string().contains("not a list row number"), "{err}");
+        let found = resolve_destructive_arg(&mut store, "3f2a", "delete").unwrap();
+        assert_eq!(found.session_id, "3f2a9c1e-row");
+    }
 
-        let sessions = vec![SessionInfo {
-            source: SessionSource::Claudecode,
-            session_id: session_id.to_string(),
-            display: "session".to_string(),
-            project: "/tmp/fulltext".to_string(),
-            timestamp: 1,
-            model: String::new(),
-            reasoning_effort: String::new(),
-            file_path: Some(path.to_string_lossy().to_string()),
-        }];
+    #[test]
+    fn private_file_is_owner_only_and_never_reused() {
+        let path = env::temp_dir().join(format!("cs-rs-private-file-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        write_private_file(&path, "transcript").expect("create private file");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "transcript");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let err = write_private_file(&path, "again").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let _ = fs::remove_file(&path);
+    }
 
-        let mut filtered = Vec::new();
-        apply_filter(&mut store, &mut filtered, &sessions, "flibbertigibbet");
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(store.search_text_cache.len(), 1);
+    #[test]
+    fn stats_glyphs_fall_back_to_ascii_for_plain_environments() {
+        let utf8 = Some("en_US.UTF-8");
+        assert!(!wants_ascii_glyphs(false, utf8, false));
+        assert!(!wants_ascii_glyphs(false, None, false));
+        assert!(wants_ascii_glyphs(true, utf8, false));
+        assert!(wants_ascii_glyphs(false, Some("C"), false));
+        assert!(wants_ascii_glyphs(false, utf8, true));
+    }
 
-        apply_filter(&mut store, &mut filtered, &sessions, "flibber");
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(store.search_text_cache.len(), 1);
+    #[test]
+    fn view_json_follows_the_same_order_as_the_text_view() {
+        let dir = env::temp_dir().join(format!("cs-rs-json-order-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        let path = dir.join("order.jsonl");
+        fs::write(
+            &path,
+            "{\"type\":\"user\",\"uuid\":\"u1\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"message\":{\"role\":\"user\",\"content\":\"first\"}}\n\
+             {\"type\":\"assistant\",\"uuid\":\"a1\",\"timestamp\":\"2026-02-13T17:00:05.000Z\",\"message\":{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{\"type\":\"text\",\"text\":\"second\"}]}}\n",
+        )
+        .expect("write fixture");
+        let session = SessionInfo {
+            display: "first".to_string(),
+            project: "/tmp/p".to_string(),
+            file_path: Some(path.to_string_lossy().to_string()),
+            ..test_session("order", SessionSource::Claudecode, 1)
+        };
+        let store = test_store();
+        let texts = |reverse: bool| -> Vec<String> {
+            let options = RenderOptions {
+                reverse,
+                ..RenderOptions::default()
+            };
+            render_conversation_json(&store, &session, options)["messages"]
+                .as_array()
+                .expect("messages array")
+                .iter()
+                .map(|msg| msg["text"].as_str().unwrap_or_default().to_string())
+                .collect()
+        };
+        assert_eq!(texts(false), ["second", "first"]);
+        assert_eq!(texts(true), ["first", "second"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-        let _ = fs::remove_file(path);
+    #[test]
+    fn file_size_map_records_sessions_with_files() {
+        let dir = env::temp_dir().join(format!("cs-rs-file-sizes-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        let path = dir.join("sized.jsonl");
+        fs::write(&path, "0123456789").expect("write fixture");
+        let session = |id: &str, file_path: Option<String>| SessionInfo {
+            project: "/tmp/p".to_string(),
+            file_path,
+            ..test_session(id, SessionSource::Claudecode, 1)
+        };
+        let sessions = [
+            session("sized", Some(path.to_string_lossy().to_string())),
+            session(
+                "gone",
+                Some(dir.join("gone.jsonl").to_string_lossy().to_string()),
+            ),
+            session("none", None),
+        ];
+        let sizes = build_file_size_map(&sessions);
+        assert_eq!(sizes.len(), 1);
+        let key = SessionSource::Claudecode.internal_key("sized");
+        assert_eq!(
+            file_size_label(sizes.get(&key).copied()),
+            ("10 B".to_string(), false)
+        );
+        assert_eq!(file_size_label(None), ("—".to_string(), false));
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn list_time_formats_relative_days() {
-        let now = Local::now();
+    fn message_count_ignores_tool_only_assistant_turns() {
+        let dir = env::temp_dir().join(format!("cs-rs-tool-only-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create fixture dir");
+        let path = dir.join("tool-only.jsonl");
+        fs::write(
+            &path,
+            "{\"type\":\"user\",\"uuid\":\"u1\",\"timestamp\":\"2026-02-13T17:00:00.000Z\",\"message\":{\"role\":\"user\",\"content\":\"list files\"}}\n\
+             {\"type\":\"assistant\",\"uuid\":\"a1\",\"timestamp\":\"2026-02-13T17:00:01.000Z\",\"message\":{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Bash\",\"input\":{\"command\":\"ls\"}}]}}\n\
+             {\"type\":\"user\",\"uuid\":\"u2\",\"timestamp\":\"2026-02-13T17:00:02.000Z\",\"message\":{\"role\":\"user\",\"content\":[{\"type\":\"tool_result\",\"tool_use_id\":\"t1\",\"content\":\"a b\"}]}}\n\
+             {\"type\":\"assistant\",\"uuid\":\"a2\",\"timestamp\":\"2026-02-13T17:00:03.000Z\",\"message\":{\"role\":\"assistant\",\"model\":\"m\",\"content\":[{\"type\":\"text\",\"text\":\"two files\"}]}}\n",
+        )
+        .expect("write fixture");
+        let session = SessionInfo {
+            display: "list files".to_string(),
+            project: "/tmp/p".to_string(),
+            file_path: Some(path.to_string_lossy().to_string()),
+            ..test_session("tool-only", SessionSource::Claudecode, 1)
+        };
+        let mut store = test_store();
+        assert_eq!(store.message_count(&session), 2);
+        assert_eq!(store.message_stats(&session).user_turns, Some(1));
+        let msgs = store.read_messages(&session, true);
+        assert!(conversation_size_line(&msgs, false).starts_with("Messages: 2  ·"));
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-        // 3 minutes 30 seconds → "3m 30s"
-        let ts = now - chrono::Duration::seconds(210);
-        assert_eq!(list_time(ts.timestamp_millis()), "3m 30s");
+    #[test]
+    fn session_tags_toggle_persist_and_filter_the_list() {
+        let root = env::temp_dir().join(format!("cs-rs-tags-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let session = |id: &str| SessionInfo {
+            display: id.to_string(),
+            project: "/tmp/p".to_string(),
+            ..test_session(id, SessionSource::Claudecode, 1)
+        };
+        let (tagged, plain) = (session("tagged-1"), session("plain-1"));
 
-        // 6 hours 23 minutes → "6h 23m"
-        let ts = now - chrono::Duration::minutes(383);
-        assert_eq!(list_time(ts.timestamp_millis()), "6h 23m");
+        let _env = DATA_DIR_ENV.lock().unwrap_or_else(|err| err.into_inner());
+        env::set_var("CS_RS_DATA_DIR", &root);
+        let mut store = test_store();
+        for s in [&tagged, &plain] {
+            store
+                .sessions
+                .insert(s.source.internal_key(&s.session_id), s.clone());
+        }
+        store.toggle_session_tag(&tagged, " bug ");
+        store.toggle_session_tag(&tagged, "spike");
+        assert_eq!(store.session_tags(&tagged), ["bug", "spike"]);
+        // Toggling an existing tag (in any case) removes it.
+        store.toggle_session_tag(&tagged, "SPIKE");
+        assert_eq!(store.session_tags(&tagged), ["bug"]);
+        store.toggle_session_tag(&plain, "bug");
+        store.toggle_session_tag(&plain, "bug");
+        assert!(store.session_tags(&plain).is_empty());
+        let persisted = SessionStore::load_cache();
+        env::remove_var("CS_RS_DATA_DIR");
 
-        // 3 hours 2 minutes → "3h 02m"
-        let ts = now - chrono::Duration::minutes(182);
-        assert_eq!(list_time(ts.timestamp_millis()), "3h 02m");
+        assert_eq!(
+            persisted
+                .tags
+                .get(&tagged.source.internal_key(&tagged.session_id)),
+            Some(&vec!["bug".to_string()])
+        );
+        assert!(!persisted
+            .tags
+            .contains_key(&plain.source.internal_key(&plain.session_id)));
 
-        // 2 days and 5 hours → "2d 05h"
-        let ts = now - chrono::Duration::hours(53);
-        assert_eq!(list_time(ts.timestamp_millis()), "2d 05h");
+        let filters = ListFilters {
+            tag: Some("Bug".to_string()),
+            ..ListFilters::default()
+        };
+        let ids: Vec<String> = filter_sessions(&mut store, &filters)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.session_id)
+            .collect();
+        assert_eq!(ids, ["tagged-1"]);
 
-        // 10 days → "10d"
-        let ts = now - chrono::Duration::days(10);
-        assert_eq!(list_time(ts.timestamp_millis()), "10d");
+        let _ = fs::remove_dir_all(root);
     }
 
     #[test]
-    fn codex_model_candidate_accepts_bracket_suffix() {
-        assert_eq!(
-            codex_model_candidate("claude-opus-4-6[1m]").as_deref(),
-            Some("claude-opus-4-6[1m]")
-        );
+    fn orphaned_session_files_ignore_sessions_named_in_history() {
+        let root = env::temp_dir().join(format!("cs-rs-orphans-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let claude = root.join(".claude");
+        let project_dir = claude.join("projects").join("-tmp-fixture");
+        fs::create_dir_all(&project_dir).unwrap();
+        let listed_id = "11111111-1111-1111-1111-111111111111";
+        let blank_id = "22222222-2222-2222-2222-222222222222";
+        let lost_id = "33333333-3333-3333-3333-333333333333";
+        // `listed_id` loads normally; `blank_id` has an empty transcript so it
+        // never reaches the session list, but history still names it.
+        fs::write(
+            claude.join("history.jsonl"),
+            format!(
+                "{{\"sessionId\":\"{listed_id}\",\"display\":\"hello\",\"timestamp\":1771002000000,\"project\":\"/tmp/fixture\"}}\n\
+                 {{\"sessionId\":\"{blank_id}\",\"display\":\"blank\",\"timestamp\":1771002000001,\"project\":\"/tmp/fixture\"}}\n"
+            ),
+        )
+        .unwrap();
+        let transcript =
+            "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hello\"}}\n";
+        fs::write(project_dir.join(format!("{listed_id}.jsonl")), transcript).unwrap();
+        fs::write(project_dir.join(format!("{blank_id}.jsonl")), "\n").unwrap();
+        fs::write(project_dir.join(format!("{lost_id}.jsonl")), transcript).unwrap();
+        // Sidechain transcripts aren't sessions of their own.
+        fs::write(project_dir.join("agent-a1b2c3.jsonl"), transcript).unwrap();
+
+        let _env = DATA_DIR_ENV.lock().unwrap_or_else(|err| err.into_inner());
+        env::set_var("CS_RS_DATA_DIR", &root);
+        let mut store = SessionStore::new();
+        let listed: Vec<String> = store.all().into_iter().map(|s| s.session_id).collect();
+        let orphans = store.orphaned_session_files();
+        env::remove_var("CS_RS_DATA_DIR");
+
+        assert_eq!(listed, [listed_id]);
         assert_eq!(
-            codex_model_candidate("claude-opus-4-6").as_deref(),
-            Some("claude-opus-4-6")
+            orphans,
+            [(
+                SessionSource::Claudecode,
+                project_dir.join(format!("{lost_id}.jsonl"))
+            )]
         );
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn open_session_file_streams_gzip_transcripts() {
+        let dir = env::temp_dir().join(format!("cs-rs-gzip-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("streamed.jsonl");
+        fs::write(&plain, "{\"n\":1}\n{\"n\":2}\n").unwrap();
+        let status = Command::new("gzip").arg("-f").arg(&plain).status();
+        if !status.is_ok_and(|s| s.success()) {
+            let _ = fs::remove_dir_all(&dir);
+            return;
+        }
+        let lines: Vec<String> = open_session_file(&dir.join("streamed.jsonl.gz"))
+            .unwrap()
+            .lines()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, ["{\"n\":1}", "{\"n\":2}"]);
+
+        let corrupt = dir.join("corrupt.jsonl.gz");
+        fs::write(&corrupt, "not gzip").unwrap();
+        let read = open_session_file(&corrupt)
+            .unwrap()
+            .lines()
+            .collect::<io::Result<Vec<String>>>();
+        assert!(read.is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn list_filter_header_totals_exclude_hidden_archived_sessions() {
+        let root = env::temp_dir().join(format!("cs-rs-filter-total-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let _env = DATA_DIR_ENV.lock().unwrap_or_else(|err| err.into_inner());
+        env::set_var("CS_RS_DATA_DIR", &root);
+        let archived_path = SessionSource::Codex
+            .archived_sessions_dir()
+            .join("rollout-old.jsonl");
+        let session = |id: &str, project: &str, file_path: Option<PathBuf>| SessionInfo {
+            display: id.to_string(),
+            project: project.to_string(),
+            file_path: file_path.map(|p| p.to_string_lossy().to_string()),
+            ..test_session(id, SessionSource::Codex, 1)
+        };
+        let mut store = test_store();
+        for s in [
+            session("kept-1", "/tmp/app", None),
+            session("other-1", "/tmp/web", None),
+            session("old-1", "/tmp/app", Some(archived_path)),
+        ] {
+            store
+                .sessions
+                .insert(s.source.internal_key(&s.session_id), s);
+        }
+        let header = |archived: bool, store: &mut SessionStore| {
+            let filters = ListFilters {
+                project: Some(ProjectFilter::Pattern("app".to_string())),
+                archived,
+                ..ListFilters::default()
+            };
+            let out = list_command(
+                store,
+                &filters,
+                0,
+                ListFormat::Table,
+                &[],
+                SessionSort::default(),
+                TableOptions::default(),
+            )
+            .unwrap();
+            out.lines().next().unwrap_or_default().to_string()
+        };
+        let hidden = header(false, &mut store);
+        let shown = header(true, &mut store);
+        env::remove_var("CS_RS_DATA_DIR");
+
+        assert_eq!(hidden, "Filters: project=app (1 of 2 sessions)");
+        assert_eq!(shown, "Filters: project=app (2 of 3 sessions)");
+        let _ = fs::remove_dir_all(root);
     }
 }